@@ -0,0 +1,65 @@
+//!
+//! Polyglot book builder
+//!
+//! Replays the games of PGN files and writes the moves played
+//! into an opening book, for use by the engine and simulator
+//!
+//! Usage: build_book [--max-ply N] [--min-count N] [--weight-by-result] -o BOOK PGN...
+//!
+
+use libchess::book::BookBuilder;
+use libchess::pgn::PgnGame;
+
+fn usage() -> ! {
+    eprintln!("Usage: build_book [--max-ply N] [--min-count N] [--weight-by-result] -o BOOK PGN...");
+    std::process::exit(1);
+}
+
+fn main() {
+    let mut builder = BookBuilder::new();
+    let mut output = None;
+    let mut inputs = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-ply" => builder.max_ply = Some(args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage())),
+            "--min-count" => builder.min_count = args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage()),
+            "--weight-by-result" => builder.weight_by_result = true,
+            "-o" | "--output" => output = Some(args.next().unwrap_or_else(|| usage())),
+            flag if flag.starts_with('-') => usage(),
+            _ => inputs.push(arg),
+        }
+    }
+    let Some(output) = output else {
+        usage();
+    };
+    if inputs.is_empty() {
+        usage();
+    }
+
+    let mut game_count = 0;
+    for path in &inputs {
+        let games = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|pgn| PgnGame::parse_all(&pgn).map_err(|e| e.to_string()));
+
+        // A bad file is reported but does not spoil the rest of the book
+        match games {
+            Ok(games) => {
+                for game in &games {
+                    builder.add_game(game);
+                }
+                game_count += games.len();
+            }
+            Err(e) => eprintln!("Skipping {path}: {e}"),
+        }
+    }
+
+    let book = builder.build();
+    if let Err(e) = book.write(&output) {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+    println!("Wrote {} entries from {game_count} games to {output}", book.len());
+}
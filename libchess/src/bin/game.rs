@@ -1,11 +1,9 @@
 fn main() {
 
-    let mut chess = libchess::game::Game::default();
-
-    let mut mb = Vec::new();
+    let chess = libchess::game::Game::default();
 
     println!("{}", chess.to_fen_str());
-    let moves = chess.get_all_moves(&mut mb).unwrap();
+    let moves = chess.moves().unwrap();
 
     for m in moves{
         println!("Move: {m:?}");
@@ -0,0 +1,316 @@
+//!
+//! UCI front end for the engine
+//!
+//! Speaks the Universal Chess Interface over stdin/stdout,
+//! so the engine can be driven by GUIs and match runners
+//!
+
+use libchess::book::Book;
+use libchess::eval::TaperedEvaluator;
+use libchess::game::Game;
+use libchess::piece::Team;
+use libchess::search::{self, SearchLimits};
+use libchess::tt::{SearchEntry, TranspositionTable};
+use std::io::BufRead;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+///
+/// Depth searched when `go` sets no limits at all
+///
+const DEFAULT_DEPTH: u32 = 5;
+
+///
+/// Moves assumed to remain when the GUI does not send `movestogo`
+///
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+#[derive(Debug, Default, Clone)]
+struct Limits {
+    depth: Option<u32>,
+    nodes: Option<u64>,
+    movetime: Option<Duration>,
+    wtime: Option<Duration>,
+    btime: Option<Duration>,
+    winc: Duration,
+    binc: Duration,
+    movestogo: Option<u32>,
+    infinite: bool,
+}
+
+impl Limits {
+    fn parse(args: &[&str]) -> Self {
+        let mut limits = Self::default();
+        let mut args = args.iter();
+
+        while let Some(&arg) = args.next() {
+            let mut number = || args.next().and_then(|v| v.parse::<u64>().ok());
+            match arg {
+                "depth" => limits.depth = number().map(|d| d as u32),
+                "nodes" => limits.nodes = number(),
+                "movetime" => limits.movetime = number().map(Duration::from_millis),
+                "wtime" => limits.wtime = number().map(Duration::from_millis),
+                "btime" => limits.btime = number().map(Duration::from_millis),
+                "winc" => limits.winc = number().map(Duration::from_millis).unwrap_or_default(),
+                "binc" => limits.binc = number().map(Duration::from_millis).unwrap_or_default(),
+                "movestogo" => limits.movestogo = number().map(|m| m as u32),
+                "infinite" => limits.infinite = true,
+                _ => {}
+            }
+        }
+        limits
+    }
+
+    ///
+    /// How long to think for, if the search is bound by time at all
+    ///
+    fn time_budget(&self, side: Team, overhead: Duration) -> Option<Duration> {
+        if self.infinite {
+            return None;
+        }
+        if let Some(movetime) = self.movetime {
+            return Some(movetime.saturating_sub(overhead));
+        }
+
+        let (remaining, increment) = match side {
+            Team::White => (self.wtime?, self.winc),
+            Team::Black => (self.btime?, self.binc),
+        };
+        let moves_to_go = self.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let budget = remaining / moves_to_go + increment / 2;
+
+        Some(budget.min(remaining.saturating_sub(overhead)).saturating_sub(overhead))
+    }
+
+    ///
+    /// Whether anything other than depth will end the search
+    ///
+    const fn is_bounded(&self) -> bool {
+        self.infinite
+            || self.nodes.is_some()
+            || self.movetime.is_some()
+            || self.wtime.is_some()
+            || self.btime.is_some()
+    }
+}
+
+///
+/// Size of the transposition table in megabytes, unless set through the Hash option
+///
+const DEFAULT_HASH_MB: usize = 16;
+
+struct Engine {
+    game: Game,
+    search: Option<(JoinHandle<()>, Arc<AtomicBool>)>,
+    tt: Arc<TranspositionTable<SearchEntry>>,
+    move_overhead: Duration,
+
+    ///
+    /// Played from while it has moves for the position, if OwnBook is set
+    ///
+    book: Option<Book>,
+    own_book: bool,
+}
+
+impl Engine {
+    fn new() -> Self {
+        Self {
+            game: Game::default(),
+            search: None,
+            tt: Arc::new(TranspositionTable::new(DEFAULT_HASH_MB)),
+            move_overhead: Duration::from_millis(10),
+            book: None,
+            own_book: false,
+        }
+    }
+
+    ///
+    /// Halt any running search, waiting for it to report its move
+    ///
+    fn stop(&mut self) {
+        if let Some((handle, stop)) = self.search.take() {
+            stop.store(true, Ordering::Relaxed);
+
+            // Wakes an infinite search which is waiting to be stopped
+            handle.thread().unpark();
+            handle.join().expect("Search thread panicked");
+        }
+    }
+
+    fn clear_hash(&mut self) {
+        self.stop();
+
+        // With the search finished nothing else holds on to the table
+        if let Some(tt) = Arc::get_mut(&mut self.tt) {
+            tt.clear();
+        }
+    }
+
+    fn set_position(&mut self, args: &[&str]) {
+        let (mut game, rest) = match args {
+            ["startpos", rest @ ..] => (Game::default(), rest),
+            ["fen", rest @ ..] => {
+                let fen_len = rest.iter().position(|&a| a == "moves").unwrap_or(rest.len());
+                match Game::from_fen_str(&rest[..fen_len].join(" ")) {
+                    Ok(game) => (game, &rest[fen_len..]),
+                    Err(e) => {
+                        println!("info string {e}");
+                        return;
+                    }
+                }
+            }
+            _ => {
+                println!("info string Expected 'startpos' or 'fen'");
+                return;
+            }
+        };
+
+        // The previous position is kept unless every move is legal,
+        // rather than searching a position the GUI never asked for
+        if let ["moves", moves @ ..] = rest {
+            for uci in moves {
+                match game.parse_uci_move(uci) {
+                    Ok(mov) => {
+                        game.make_move(mov);
+                    }
+                    Err(e) => {
+                        println!("info string {e}");
+                        return;
+                    }
+                }
+            }
+        }
+        self.game = game;
+    }
+
+    fn go(&mut self, args: &[&str]) {
+        self.stop();
+
+        if let Some(book) = self.book.as_ref().filter(|_| self.own_book) {
+            if let Some(mov) = book.pick_weighted(&self.game, &mut rand::thread_rng()) {
+                println!("bestmove {}", mov.to_uci());
+                return;
+            }
+        }
+
+        let limits = Limits::parse(args);
+        let search_limits = SearchLimits {
+            depth: limits.depth.or(if limits.is_bounded() { None } else { Some(DEFAULT_DEPTH) }),
+            nodes: limits.nodes,
+            time: limits.time_budget(self.game.side_to_move(), self.move_overhead),
+        };
+        let stop = Arc::new(AtomicBool::new(false));
+        let game = self.game.clone();
+        let thread_stop = stop.clone();
+        let tt = self.tt.clone();
+
+        let handle = std::thread::spawn(move || {
+            let report = search::search(&game, &TaperedEvaluator, &tt, search_limits, &thread_stop, |report| {
+                let millis = report.elapsed.as_millis();
+                let pv: Vec<_> = report.pv.iter().map(|m| m.to_uci()).collect();
+                println!(
+                    "info depth {} score {} nodes {} nps {} hashfull {} time {millis} pv {}",
+                    report.depth,
+                    report.score,
+                    report.nodes,
+                    u128::from(report.nodes) * 1000 / millis.max(1),
+                    tt.hashfull(),
+                    pv.join(" ")
+                );
+            });
+
+            // An infinite search may only report its move once told to stop,
+            // parking until `Engine::stop` unparks it
+            while limits.infinite && !thread_stop.load(Ordering::Relaxed) {
+                std::thread::park();
+            }
+            println!("bestmove {}", report.best_move().map_or("0000".to_owned(), |m| m.to_uci()));
+        });
+        self.search = Some((handle, stop));
+    }
+
+    fn set_option(&mut self, args: &[&str]) {
+        let value_at = args.iter().position(|&a| a == "value");
+        let name = args[..value_at.unwrap_or(args.len())]
+            .iter()
+            .skip_while(|&&a| a == "name")
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let value = value_at.map(|i| args[i + 1..].join(" ")).unwrap_or_default();
+
+        match name.to_lowercase().as_str() {
+            "hash" => match value.parse() {
+                Ok(mb) => {
+                    self.stop();
+                    self.tt = Arc::new(TranspositionTable::new(mb));
+                }
+                Err(_) => println!("info string Invalid value '{value}' for {name}"),
+            },
+            "clear hash" => self.clear_hash(),
+            "move overhead" => match value.parse() {
+                Ok(ms) => self.move_overhead = Duration::from_millis(ms),
+                Err(_) => println!("info string Invalid value '{value}' for {name}"),
+            },
+            "ownbook" => match value.parse() {
+                Ok(own_book) => self.own_book = own_book,
+                Err(_) => println!("info string Invalid value '{value}' for {name}"),
+            },
+            "bookfile" => {
+                self.book = match value.as_str() {
+                    "" | "<empty>" => None,
+                    path => match Book::open(path) {
+                        Ok(book) => Some(book),
+                        Err(e) => {
+                            println!("info string {e}");
+                            None
+                        }
+                    },
+                };
+            }
+            _ => println!("info string Unknown option '{name}'"),
+        }
+    }
+}
+
+fn main() {
+    let mut engine = Engine::new();
+
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let args: Vec<&str> = line.split_whitespace().collect();
+
+        match args.as_slice() {
+            ["uci"] => {
+                println!("id name libchess {}", env!("CARGO_PKG_VERSION"));
+                println!("id author {}", env!("CARGO_PKG_AUTHORS"));
+                println!("option name Hash type spin default {DEFAULT_HASH_MB} min 1 max 4096");
+                println!("option name Clear Hash type button");
+                println!("option name Move Overhead type spin default 10 min 0 max 5000");
+                println!("option name OwnBook type check default false");
+                println!("option name BookFile type string default <empty>");
+                println!("uciok");
+            }
+            ["isready"] => println!("readyok"),
+            ["ucinewgame"] => {
+                engine.clear_hash();
+                engine.game = Game::default();
+            }
+            ["position", rest @ ..] => {
+                engine.stop();
+                engine.set_position(rest);
+            }
+            ["go", rest @ ..] => engine.go(rest),
+            ["stop"] => engine.stop(),
+            ["setoption", rest @ ..] => engine.set_option(rest),
+            ["quit"] => break,
+            [] | ["debug", ..] => {}
+            [command, ..] => println!("info string Unknown command '{command}'"),
+        }
+    }
+    engine.stop();
+}
@@ -1,9 +1,15 @@
+use crate::game::FenParseError;
 use crate::movement;
 use crate::piece::*;
 use positioning::Bitboard;
 use positioning::Position;
 
-#[derive(Clone, Copy, Debug)]
+///
+/// Squares of the same colour as A1
+///
+const DARK_SQUARES: Bitboard = Bitboard::from_bits(0xAA55_AA55_AA55_AA55);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ChessBoard {
     pub kings: Bitboard,
     pub queens: Bitboard,
@@ -117,6 +123,105 @@ impl ChessBoard {
         ret
     }
 
+    ///
+    /// Parse the piece placement field of a FEN string
+    ///
+    /// Ranks are listed from the 8th down to the 1st,
+    /// each king must be present exactly once
+    ///
+    pub fn from_fen(placement: &str) -> Result<Self, FenParseError> {
+        let mut board = Self {
+            kings: Bitboard::default(),
+            queens: Bitboard::default(),
+            knights: Bitboard::default(),
+            rooks: Bitboard::default(),
+            bishops: Bitboard::default(),
+            pawns: Bitboard::default(),
+            blacks: Bitboard::default(),
+            whites: Bitboard::default(),
+        };
+
+        let ranks: Vec<_> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenParseError::BadRankCount(ranks.len()));
+        }
+
+        for (rank, row) in ranks.into_iter().zip((0..8).rev()) {
+            let mut col = 0u8;
+            for ch in rank.chars() {
+                if let Some(skip) = ch.to_digit(10) {
+                    // Checked before adding, as a long run of digits would overflow `col`
+                    if !(1..=8).contains(&skip) || u32::from(col) + skip > 8 {
+                        return Err(FenParseError::BadRankLength { rank: row + 1 });
+                    }
+                    col += skip as u8;
+                    continue;
+                }
+
+                if col >= 8 {
+                    return Err(FenParseError::BadRankLength { rank: row + 1 });
+                }
+
+                let kind = match ch.to_ascii_lowercase() {
+                    'k' => PieceKind::King,
+                    'q' => PieceKind::Queen,
+                    'r' => PieceKind::Rook,
+                    'b' => PieceKind::Bishop,
+                    'n' => PieceKind::Knight,
+                    'p' => PieceKind::Pawn,
+                    _ => return Err(FenParseError::UnknownPiece(ch)),
+                };
+                let team = if ch.is_ascii_uppercase() {
+                    Team::White
+                } else {
+                    Team::Black
+                };
+
+                let bb = Bitboard::from(Position::new(row, col));
+                *board.get_mut_team_bb_rt(team) |= bb;
+                *board.get_mut_piece_bb_rt(kind) |= bb;
+                col += 1;
+            }
+
+            if col != 8 {
+                return Err(FenParseError::BadRankLength { rank: row + 1 });
+            }
+        }
+
+        for team in [Team::White, Team::Black] {
+            let team_bb = match team {
+                Team::White => board.whites,
+                Team::Black => board.blacks,
+            };
+            let count = (team_bb & board.kings).count();
+            if count != 1 {
+                return Err(FenParseError::BadKingCount { team, count });
+            }
+        }
+
+        Ok(board)
+    }
+
+    ///
+    /// Whether neither side could possibly deliver checkmate,
+    /// i.e only kings remain alongside a single minor piece,
+    /// or bishops which all share a square colour
+    ///
+    pub fn has_insufficient_material(&self) -> bool {
+        if !(self.pawns | self.rooks | self.queens).empty() {
+            return false;
+        }
+
+        let minors = self.knights | self.bishops;
+        if minors.count() <= 1 {
+            return true;
+        }
+
+        self.knights.empty()
+            && ((self.bishops & DARK_SQUARES).empty()
+                || self.bishops.where_not(DARK_SQUARES).empty())
+    }
+
     pub const fn team_pieces<T: TTeam>(&self) -> Bitboard {
         match T::TEAM {
             Team::Black => self.blacks,
@@ -124,6 +229,12 @@ impl ChessBoard {
         }
     }
 
+    ///
+    /// The legal destinations of a piece, found by playing out each
+    /// pseudo-move on a copy of the board
+    ///
+    /// This is slow, and kept as a reference for `get_piece_masked_moves`
+    ///
     pub fn get_piece_legal_moves<T: TTeam, K: TPieceKind>(&self, pos: Position) -> Bitboard {
         let pseudo_moves = self.get_piece_pseudo_moves::<T, K>(pos);
         let pos_mask = Bitboard::from(pos);
@@ -146,6 +257,124 @@ impl ChessBoard {
         ret
     }
 
+    ///
+    /// Work out what constrains the moves of a team in the position
+    ///
+    pub fn legality_masks<T: TTeam>(&self) -> LegalityMasks {
+        let king = self.pieces::<T, GKing>().piece_position();
+        let friends = self.team_pieces::<T>();
+        let enemies = self.team_pieces::<T::Enemy>();
+        let world = friends | enemies;
+
+        let checkers = self.checkers::<T>();
+        let check_mask = match checkers.count() {
+            0 => Bitboard::from_bits(u64::MAX),
+            1 => checkers | movement::between(king, checkers.piece_position()),
+            _ => Bitboard::default(),
+        };
+
+        let pinned = self.pinned::<T>();
+
+        // The king must not be counted as blocking the sliders it steps away from
+        let king_danger = self.attack_map::<T::Enemy>(world.where_not(Bitboard::from(king)));
+
+        LegalityMasks {
+            king,
+            checkers,
+            check_mask,
+            pinned,
+            king_danger,
+        }
+    }
+
+    ///
+    /// The enemy pieces giving check to the king of the team
+    ///
+    pub fn checkers<T: TTeam>(&self) -> Bitboard {
+        let king = self.pieces::<T, GKing>().piece_position();
+        self.pos_attackers::<T::Enemy>(king)
+    }
+
+    ///
+    /// The pieces of the team pinned to their king, each with the enemy slider pinning it
+    ///
+    pub fn pins<T: TTeam>(&self) -> impl Iterator<Item = Pin> {
+        let king = self.pieces::<T, GKing>().piece_position();
+        let enemies = self.team_pieces::<T::Enemy>();
+        let world = self.whites | self.blacks;
+
+        // Sliders seeing the king through nothing but friendly pieces
+        // pin the piece between them when there is only the one
+        let rooklikes = (self.rooks | self.queens) & enemies;
+        let bishlikes = (self.bishops | self.queens) & enemies;
+        let snipers = (movement::rook_moves(king, enemies) & rooklikes)
+            | (movement::bishop_moves(king, enemies) & bishlikes);
+
+        snipers.positions().filter_map(move |pinner| {
+            let blockers = movement::between(king, pinner) & world;
+            (blockers.count() == 1).then(|| Pin {
+                pinned: blockers.piece_position(),
+                pinner,
+            })
+        })
+    }
+
+    ///
+    /// The pieces of the team pinned to their king
+    ///
+    pub fn pinned<T: TTeam>(&self) -> Bitboard {
+        self.pins::<T>()
+            .fold(Bitboard::default(), |pinned, pin| pinned | Bitboard::from(pin.pinned))
+    }
+
+    ///
+    /// Pieces of either team standing alone between the king of the team
+    /// and an enemy slider, which would expose the king by moving off the line
+    ///
+    pub(crate) fn king_blockers<T: TTeam>(&self) -> Bitboard {
+        let king = self.pieces::<T, GKing>().piece_position();
+        let enemies = self.team_pieces::<T::Enemy>();
+        let world = self.whites | self.blacks;
+
+        let rooklikes = (self.rooks | self.queens) & enemies;
+        let bishlikes = (self.bishops | self.queens) & enemies;
+        let snipers = (movement::rook_moves(king, Bitboard::default()) & rooklikes)
+            | (movement::bishop_moves(king, Bitboard::default()) & bishlikes);
+
+        let mut blockers = Bitboard::default();
+        for sniper in snipers.positions() {
+            let between = movement::between(king, sniper) & world;
+            if between.count() == 1 {
+                blockers |= between;
+            }
+        }
+        blockers
+    }
+
+    ///
+    /// The legal destinations of a piece, given the masks of its team
+    ///
+    /// En passant and castling are not covered,
+    /// as neither shows up in the pseudo-moves
+    ///
+    pub fn get_piece_masked_moves<T: TTeam, K: TPieceKind>(
+        &self,
+        pos: Position,
+        masks: &LegalityMasks,
+    ) -> Bitboard {
+        let pseudo_moves = self.get_piece_pseudo_moves::<T, K>(pos);
+        if K::KIND == PieceKind::King {
+            return pseudo_moves.where_not(masks.king_danger);
+        }
+
+        let legal = pseudo_moves & masks.check_mask;
+        if (masks.pinned & Bitboard::from(pos)).empty() {
+            legal
+        } else {
+            legal & movement::line(masks.king, pos)
+        }
+    }
+
     pub fn get_piece_info(&self, piece: Position) -> Option<PieceInfo> {
         let b = Bitboard::from(piece);
         let f = |d: Bitboard| !(b & d).empty();
@@ -204,14 +433,14 @@ impl ChessBoard {
         .where_not(friends)
     }
 
-    pub(crate) fn get_mut_team_bb_rt(&mut self, team: Team) -> &mut Bitboard {
+    pub(crate) const fn get_mut_team_bb_rt(&mut self, team: Team) -> &mut Bitboard {
         match team {
             Team::White => &mut self.whites,
             Team::Black => &mut self.blacks,
         }
     }
 
-    pub(crate) fn get_mut_piece_bb_rt(&mut self, kind: PieceKind) -> &mut Bitboard {
+    pub(crate) const fn get_mut_piece_bb_rt(&mut self, kind: PieceKind) -> &mut Bitboard {
         match kind {
             PieceKind::King => &mut self.kings,
             PieceKind::Rook => &mut self.rooks,
@@ -222,14 +451,14 @@ impl ChessBoard {
         }
     }
 
-    pub(crate) fn get_mut_team_bb<T: TTeam>(&mut self) -> &mut Bitboard {
+    pub(crate) const fn get_mut_team_bb<T: TTeam>(&mut self) -> &mut Bitboard {
         match T::TEAM {
             Team::White => &mut self.whites,
             Team::Black => &mut self.blacks,
         }
     }
 
-    pub(crate) fn get_mut_piece_bb<K: TPieceKind>(&mut self) -> &mut Bitboard {
+    pub(crate) const fn get_mut_piece_bb<K: TPieceKind>(&mut self) -> &mut Bitboard {
         match K::KIND {
             PieceKind::King => &mut self.kings,
             PieceKind::Rook => &mut self.rooks,
@@ -258,7 +487,10 @@ impl ChessBoard {
         self.blacks &= neg;
     }
 
-    pub fn pos_attacker_cnt<AtkBy : TTeam>(&self, pos : Position) -> u8{
+    ///
+    /// The pieces of the team attacking the position
+    ///
+    pub fn pos_attackers<AtkBy : TTeam>(&self, pos : Position) -> Bitboard{
         let world = self.whites | self.blacks;
         let enemies = self.team_pieces::<AtkBy>();
 
@@ -292,12 +524,72 @@ impl ChessBoard {
         let killer_pawns = moves_from_pawn & pawns;
         let killer_queens = moves_as_queen & queens;
 
-        (killer_knights
+        killer_knights
             | killer_bishes
             | killer_rooks
             | killer_kings
             | killer_pawns
-            | killer_queens).count()
+            | killer_queens
+    }
+
+    ///
+    /// Every square attacked by the team
+    ///
+    /// Squares holding pieces of the team count as attacked when they are
+    /// defended, so this also tells which pieces are protected
+    ///
+    pub fn attacked_squares<AtkBy: TTeam>(&self) -> Bitboard {
+        self.attack_map::<AtkBy>(self.whites | self.blacks)
+    }
+
+    ///
+    /// Sliders of the team which would attack the position once the first
+    /// piece in their way is gone, such as the rook behind another on a file
+    ///
+    /// The pieces already attacking the position are left out
+    ///
+    pub fn xray_attackers<AtkBy: TTeam>(&self, pos: Position) -> Bitboard {
+        let world = self.whites | self.blacks;
+        let attackers = self.team_pieces::<AtkBy>();
+        let rooklikes = (self.rooks | self.queens) & attackers;
+        let bishlikes = (self.bishops | self.queens) & attackers;
+
+        let rook_rays = movement::rook_moves(pos, world);
+        let bish_rays = movement::bishop_moves(pos, world);
+        let xray_rook = movement::rook_moves(pos, world.where_not(rook_rays)).where_not(rook_rays);
+        let xray_bish = movement::bishop_moves(pos, world.where_not(bish_rays)).where_not(bish_rays);
+
+        (xray_rook & rooklikes) | (xray_bish & bishlikes)
+    }
+
+    ///
+    /// Every square attacked by the team, with sliders
+    /// seeing through anything not in the world
+    ///
+    pub(crate) fn attack_map<AtkBy: TTeam>(&self, world: Bitboard) -> Bitboard {
+        let attackers = self.team_pieces::<AtkBy>();
+        let mut attacks = Bitboard::default();
+
+        for pos in (self.pawns & attackers).positions() {
+            attacks |= movement::pawn_attackers::<AtkBy>(pos);
+        }
+        for pos in (self.knights & attackers).positions() {
+            attacks |= movement::knight_moves(pos);
+        }
+        for pos in (self.kings & attackers).positions() {
+            attacks |= movement::king_moves(pos);
+        }
+        for pos in ((self.rooks | self.queens) & attackers).positions() {
+            attacks |= movement::rook_moves(pos, world);
+        }
+        for pos in ((self.bishops | self.queens) & attackers).positions() {
+            attacks |= movement::bishop_moves(pos, world);
+        }
+        attacks
+    }
+
+    pub fn pos_attacker_cnt<AtkBy : TTeam>(&self, pos : Position) -> u8{
+        self.pos_attackers::<AtkBy>(pos).count()
     }
     pub fn is_pos_attacked<AtkBy: TTeam>(&self, pos: Position) -> bool {
         let world = self.whites | self.blacks;
@@ -350,3 +642,43 @@ impl ChessBoard {
         !self.is_pos_attacked::<T::Enemy>(king_idx)
     }
 }
+
+///
+/// A piece which cannot leave the line between its king and an enemy slider
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pin {
+    pub pinned: Position,
+    pub pinner: Position,
+}
+
+///
+/// What constrains the moves of a team in a position,
+/// worked out once so each piece's moves can be filtered with masks
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LegalityMasks {
+    pub king: Position,
+
+    ///
+    /// The enemy pieces giving check
+    ///
+    pub checkers: Bitboard,
+
+    ///
+    /// Where a piece other than the king may move to: anywhere when not in check,
+    /// onto the checker or between it and the king when in check,
+    /// and nowhere when in double check
+    ///
+    pub check_mask: Bitboard,
+
+    ///
+    /// Pieces which may only move along the line between the king and their pinner
+    ///
+    pub pinned: Bitboard,
+
+    ///
+    /// Squares attacked by the enemy, which the king may not step onto
+    ///
+    pub king_danger: Bitboard,
+}
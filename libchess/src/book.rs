@@ -0,0 +1,322 @@
+//!
+//! Polyglot opening books
+//!
+//! A book is a list of 16 byte entries sorted by the zobrist hash of the
+//! position they apply to, each suggesting one move with a weight.
+//! Books can be read, or built up from collections of games
+//!
+
+use crate::game::{Game, GameMove, Promotion};
+use crate::pgn::PgnGame;
+use crate::piece::Team;
+use positioning::Position;
+use rand::Rng;
+use std::collections::HashMap;
+use std::path::Path;
+
+///
+/// Size of a single book entry on disk
+///
+pub const ENTRY_SIZE: usize = 16;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BookError {
+    #[error("Failed to access book: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Book size of {0} bytes is not a whole number of entries")]
+    Truncated(usize),
+}
+
+///
+/// A single suggestion of the book, stored big-endian on disk
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookEntry {
+    pub key: u64,
+
+    ///
+    /// The move, packed as the destination square in the low 6 bits,
+    /// then the origin square, then the promotion piece
+    ///
+    pub mov: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    pub fn from_bytes(bytes: &[u8; ENTRY_SIZE]) -> Self {
+        let (key, rest) = bytes.split_at(8);
+        let (mov, rest) = rest.split_at(2);
+        let (weight, learn) = rest.split_at(2);
+        Self {
+            key: u64::from_be_bytes(key.try_into().unwrap()),
+            mov: u16::from_be_bytes(mov.try_into().unwrap()),
+            weight: u16::from_be_bytes(weight.try_into().unwrap()),
+            learn: u32::from_be_bytes(learn.try_into().unwrap()),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.mov.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+///
+/// Pack a move in the book's format
+///
+/// Castling is written as the king capturing its own rook
+///
+pub fn encode_move(gmove: GameMove) -> u16 {
+    let square = |pos: Position| u16::from(pos.integral());
+    let (from, to) = match gmove {
+        GameMove::CastleKingside(team) | GameMove::CastleQueenside(team) => {
+            let row = match team {
+                Team::White => 0,
+                Team::Black => 7,
+            };
+            let rook_col = if matches!(gmove, GameMove::CastleKingside(_)) { 7 } else { 0 };
+            (Position::new(row, 4), Position::new(row, rook_col))
+        }
+        _ => (gmove.from_pos(), gmove.to_pos()),
+    };
+    let promotion = match gmove.promotion() {
+        None => 0,
+        Some(Promotion::Knight) => 1,
+        Some(Promotion::Bishop) => 2,
+        Some(Promotion::Rook) => 3,
+        Some(Promotion::Queen) => 4,
+    };
+    square(to) | square(from) << 6 | promotion << 12
+}
+
+///
+/// Unpack a move of the book, if it is legal in the game
+///
+pub fn decode_move(game: &Game, mov: u16) -> Option<GameMove> {
+    // Only the move fields are compared, the top bit is unused
+    let mov = mov & 0x7FFF;
+    game.legal_moves()
+        .iter()
+        .copied()
+        .find(|&m| encode_move(m) == mov)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, BookError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(BookError::Truncated(bytes.len()));
+        }
+        let entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| BookEntry::from_bytes(chunk.try_into().unwrap()))
+            .collect();
+        Ok(Self::from_entries(entries))
+    }
+
+    ///
+    /// Build a book from entries in any order
+    ///
+    pub fn from_entries(mut entries: Vec<BookEntry>) -> Self {
+        // Stable, so entries of a position keep their order
+        entries.sort_by_key(|e| e.key);
+        Self { entries }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries.iter().flat_map(BookEntry::to_bytes).collect()
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), BookError> {
+        Ok(std::fs::write(path, self.to_bytes())?)
+    }
+
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    ///
+    /// All entries for the current position of the game
+    ///
+    pub fn entries(&self, game: &Game) -> &[BookEntry] {
+        let key = game.hash().value();
+        let start = self.entries.partition_point(|e| e.key < key);
+        let end = start + self.entries[start..].partition_point(|e| e.key == key);
+        &self.entries[start..end]
+    }
+
+    ///
+    /// The book moves for the game along with their weights,
+    /// skipping any which are not legal in the position
+    ///
+    pub fn moves(&self, game: &Game) -> Vec<(GameMove, u16)> {
+        self.entries(game)
+            .iter()
+            .filter_map(|e| Some((decode_move(game, e.mov)?, e.weight)))
+            .collect()
+    }
+
+    ///
+    /// Pick a book move with probability proportional to its weight,
+    /// none being picked when every move has a weight of zero
+    ///
+    pub fn pick_weighted(&self, game: &Game, rng: &mut impl Rng) -> Option<GameMove> {
+        let moves = self.moves(game);
+        let total: u32 = moves.iter().map(|&(_, w)| u32::from(w)).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut choice = rng.gen_range(0..total);
+        moves.into_iter().find_map(|(m, w)| {
+            if choice < u32::from(w) {
+                Some(m)
+            } else {
+                choice -= u32::from(w);
+                None
+            }
+        })
+    }
+
+    ///
+    /// Pick any of the book moves, regardless of weight
+    ///
+    pub fn pick_uniform(&self, game: &Game, rng: &mut impl Rng) -> Option<GameMove> {
+        let moves = self.moves(game);
+        if moves.is_empty() {
+            return None;
+        }
+        Some(moves[rng.gen_range(0..moves.len())].0)
+    }
+}
+
+///
+/// How often a move was played from a position, and how well it scored
+///
+#[derive(Debug, Clone, Copy, Default)]
+struct MoveStats {
+    count: u32,
+
+    ///
+    /// Two points for each win of the side playing the move, one for each draw
+    ///
+    points: u32,
+}
+
+///
+/// Gathers the moves of many games into a book
+///
+#[derive(Debug, Clone, Default)]
+pub struct BookBuilder {
+    stats: HashMap<(u64, u16), MoveStats>,
+
+    ///
+    /// Moves beyond this many plies into a game are left out
+    ///
+    pub max_ply: Option<usize>,
+
+    ///
+    /// Moves played fewer times than this are left out
+    ///
+    pub min_count: u32,
+
+    ///
+    /// Weight moves by the points they scored, rather than by how often they were played
+    ///
+    pub weight_by_result: bool,
+}
+
+impl BookBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Record the main line of a game
+    ///
+    /// Unfinished games score as draws for both sides
+    ///
+    pub fn add_game(&mut self, pgn: &PgnGame) {
+        let white_points = match pgn.result() {
+            "1-0" => 2,
+            "0-1" => 0,
+            _ => 1,
+        };
+
+        let mut game = pgn.start.clone();
+        for (ply, mov) in pgn.mainline().enumerate() {
+            if self.max_ply.is_some_and(|max| ply >= max) {
+                break;
+            }
+            let points = match game.side_to_move() {
+                Team::White => white_points,
+                Team::Black => 2 - white_points,
+            };
+
+            let stats = self.stats.entry((game.hash().value(), encode_move(mov))).or_default();
+            stats.count += 1;
+            stats.points += points;
+
+            game = game.after_move(mov);
+        }
+    }
+
+    ///
+    /// Build the book, with the moves of each position
+    /// ordered from the heaviest to the lightest
+    ///
+    pub fn build(&self) -> Book {
+        let weight = |stats: &MoveStats| {
+            if self.weight_by_result {
+                stats.points
+            } else {
+                stats.count
+            }
+        };
+
+        let kept: Vec<_> = self
+            .stats
+            .iter()
+            .filter(|(_, stats)| stats.count >= self.min_count.max(1))
+            .collect();
+
+        // Weights are scaled down together when the heaviest does not fit
+        let heaviest = kept.iter().map(|(_, stats)| weight(stats)).max().unwrap_or_default();
+        let scale = |w: u32| {
+            if heaviest <= u32::from(u16::MAX) {
+                w as u16
+            } else {
+                (u64::from(w) * u64::from(u16::MAX) / u64::from(heaviest)) as u16
+            }
+        };
+
+        let mut entries: Vec<_> = kept
+            .into_iter()
+            .map(|(&(key, mov), stats)| BookEntry {
+                key,
+                mov,
+                weight: scale(weight(stats)),
+                learn: 0,
+            })
+            .collect();
+        entries.sort_by_key(|e| (e.key, std::cmp::Reverse(e.weight), e.mov));
+        Book { entries }
+    }
+}
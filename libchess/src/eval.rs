@@ -0,0 +1,464 @@
+//!
+//! Static evaluation of positions
+//!
+//! Evaluators score a board in centipawns from the perspective
+//! of the side to move, and are shared by the search and the simulator
+//!
+
+use crate::board::ChessBoard;
+use crate::movement;
+use crate::piece::{GBlack, GWhite, PieceKind, Team};
+use positioning::{Bitboard, Position};
+use std::ops;
+
+pub trait Evaluator {
+    ///
+    /// Score the board in centipawns,
+    /// positive when `side` is better off
+    ///
+    fn evaluate(&self, board: &ChessBoard, side: Team) -> i32;
+}
+
+///
+/// Counts material and nothing else
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MaterialEvaluator;
+
+///
+/// Material, piece-square tables, mobility, pawn structure and king safety,
+/// each with separate midgame and endgame weights blended by game phase
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TaperedEvaluator;
+
+pub const fn piece_value(kind: PieceKind) -> i32 {
+    match kind {
+        PieceKind::Pawn => 100,
+        PieceKind::Knight => 320,
+        PieceKind::Bishop => 330,
+        PieceKind::Rook => 500,
+        PieceKind::Queen => 900,
+        PieceKind::King => 0,
+    }
+}
+
+const fn team_bb(board: &ChessBoard, team: Team) -> Bitboard {
+    match team {
+        Team::White => board.whites,
+        Team::Black => board.blacks,
+    }
+}
+
+const fn relative(score: i32, side: Team) -> i32 {
+    match side {
+        Team::White => score,
+        Team::Black => -score,
+    }
+}
+
+impl Evaluator for MaterialEvaluator {
+    fn evaluate(&self, board: &ChessBoard, side: Team) -> i32 {
+        let kinds = [
+            (board.pawns, PieceKind::Pawn),
+            (board.knights, PieceKind::Knight),
+            (board.bishops, PieceKind::Bishop),
+            (board.rooks, PieceKind::Rook),
+            (board.queens, PieceKind::Queen),
+        ];
+        let white = kinds
+            .iter()
+            .map(|&(pieces, kind)| {
+                piece_value(kind) * (i32::from((pieces & board.whites).count()) - i32::from((pieces & board.blacks).count()))
+            })
+            .sum();
+
+        relative(white, side)
+    }
+}
+
+///
+/// A pair of midgame and endgame scores
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Phased {
+    mg: i32,
+    eg: i32,
+}
+
+const fn phased(mg: i32, eg: i32) -> Phased {
+    Phased { mg, eg }
+}
+
+impl ops::Add for Phased {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        phased(self.mg + rhs.mg, self.eg + rhs.eg)
+    }
+}
+
+impl ops::Sub for Phased {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        phased(self.mg - rhs.mg, self.eg - rhs.eg)
+    }
+}
+
+impl ops::Mul<i32> for Phased {
+    type Output = Self;
+    fn mul(self, rhs: i32) -> Self {
+        phased(self.mg * rhs, self.eg * rhs)
+    }
+}
+
+impl ops::AddAssign for Phased {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+///
+/// The phase of a game with all minor and major pieces still on the board
+///
+const MAX_PHASE: i32 = 24;
+
+const KINDS: [PieceKind; 6] = [
+    PieceKind::Pawn,
+    PieceKind::Knight,
+    PieceKind::Bishop,
+    PieceKind::Rook,
+    PieceKind::Queen,
+    PieceKind::King,
+];
+
+const fn kind_index(kind: PieceKind) -> usize {
+    match kind {
+        PieceKind::Pawn => 0,
+        PieceKind::Knight => 1,
+        PieceKind::Bishop => 2,
+        PieceKind::Rook => 3,
+        PieceKind::Queen => 4,
+        PieceKind::King => 5,
+    }
+}
+
+const PIECE_VALUES: [Phased; 6] = [
+    phased(82, 94),
+    phased(337, 281),
+    phased(365, 297),
+    phased(477, 512),
+    phased(1025, 936),
+    phased(0, 0),
+];
+
+const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+
+// Piece-square tables are laid out as seen from White's side of the board,
+// so the first row of each table is the eighth rank
+
+#[rustfmt::skip]
+const PAWN_MG: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const PAWN_EG: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    60,  60,  60,  60,  60,  60,  60,  60,
+    40,  40,  40,  40,  40,  40,  40,  40,
+    25,  25,  25,  25,  25,  25,  25,  25,
+    15,  15,  15,  15,  15,  15,  15,  15,
+     5,   5,   5,   5,   5,   5,   5,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MG: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_EG: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+const MG_TABLES: [&[i32; 64]; 6] = [&PAWN_MG, &KNIGHT, &BISHOP, &ROOK, &QUEEN, &KING_MG];
+const EG_TABLES: [&[i32; 64]; 6] = [&PAWN_EG, &KNIGHT, &BISHOP, &ROOK, &QUEEN, &KING_EG];
+
+///
+/// Bonus per square a piece can move to, indexed by piece kind
+///
+const MOBILITY: [Phased; 6] = [
+    phased(0, 0),
+    phased(4, 4),
+    phased(5, 5),
+    phased(2, 4),
+    phased(1, 2),
+    phased(0, 0),
+];
+
+///
+/// Weight of a piece's attacks on the squares around the enemy king
+///
+const KING_ATTACK_WEIGHTS: [i32; 6] = [0, 2, 2, 3, 5, 0];
+const MAX_KING_DANGER: i32 = 500;
+const PAWN_SHIELD_BONUS: i32 = 12;
+
+const DOUBLED_PAWN: Phased = phased(-10, -20);
+const ISOLATED_PAWN: Phased = phased(-15, -10);
+
+///
+/// Bonus for a passed pawn, indexed by how far it has advanced
+///
+const PASSED_PAWN: [Phased; 8] = [
+    phased(0, 0),
+    phased(5, 10),
+    phased(10, 15),
+    phased(15, 25),
+    phased(25, 45),
+    phased(40, 70),
+    phased(60, 110),
+    phased(0, 0),
+];
+
+///
+/// How far a piece is from its own back rank
+///
+const fn relative_row(pos: Position, team: Team) -> u8 {
+    match team {
+        Team::White => pos.row(),
+        Team::Black => 7 - pos.row(),
+    }
+}
+
+const fn table_index(pos: Position, team: Team) -> usize {
+    (7 - relative_row(pos, team) as usize) * 8 + pos.col() as usize
+}
+
+const fn kind_bb(board: &ChessBoard, kind: PieceKind) -> Bitboard {
+    match kind {
+        PieceKind::Pawn => board.pawns,
+        PieceKind::Knight => board.knights,
+        PieceKind::Bishop => board.bishops,
+        PieceKind::Rook => board.rooks,
+        PieceKind::Queen => board.queens,
+        PieceKind::King => board.kings,
+    }
+}
+
+fn pawn_attacks(pawns: Bitboard, team: Team) -> Bitboard {
+    pawns.positions().fold(Bitboard::default(), |acc, pos| {
+        acc | match team {
+            Team::White => movement::pawn_attackers::<GWhite>(pos),
+            Team::Black => movement::pawn_attackers::<GBlack>(pos),
+        }
+    })
+}
+
+fn piece_attacks(kind: PieceKind, pos: Position, world: Bitboard) -> Bitboard {
+    match kind {
+        PieceKind::Knight => movement::knight_moves(pos),
+        PieceKind::Bishop => movement::bishop_moves(pos, world),
+        PieceKind::Rook => movement::rook_moves(pos, world),
+        PieceKind::Queen => movement::queen_moves(pos, world),
+        PieceKind::King => movement::king_moves(pos),
+        PieceKind::Pawn => Bitboard::default(),
+    }
+}
+
+///
+/// Every column within one of `col`, including itself
+///
+fn neighbouring_cols(col: u8) -> Bitboard {
+    (col.saturating_sub(1)..=(col + 1).min(7)).fold(Bitboard::default(), |acc, c| acc | Bitboard::from_col(c))
+}
+
+///
+/// Every row strictly in front of `pos` from `team`'s point of view
+///
+fn rows_ahead(pos: Position, team: Team) -> Bitboard {
+    let rows = match team {
+        Team::White => pos.row() + 1..8,
+        Team::Black => 0..pos.row(),
+    };
+    rows.fold(Bitboard::default(), |acc, r| acc | Bitboard::from_row(r))
+}
+
+impl TaperedEvaluator {
+    fn material_and_placement(board: &ChessBoard, team: Team) -> Phased {
+        let mut score = Phased::default();
+        for kind in KINDS {
+            let idx = kind_index(kind);
+            for pos in (kind_bb(board, kind) & team_bb(board, team)).positions() {
+                let square = table_index(pos, team);
+                score += PIECE_VALUES[idx] + phased(MG_TABLES[idx][square], EG_TABLES[idx][square]);
+            }
+        }
+        score
+    }
+
+    ///
+    /// Mobility of the team's pieces, and how much danger
+    /// they put the enemy king in
+    ///
+    fn activity(board: &ChessBoard, team: Team) -> (Phased, i32) {
+        let world = board.whites | board.blacks;
+        let own = team_bb(board, team);
+        let enemy = team_bb(board, team.enemy());
+
+        // Squares guarded by enemy pawns are not worth counting
+        let safe = (own | pawn_attacks(board.pawns & enemy, team.enemy())).negative();
+        let enemy_king = board.kings & enemy;
+        let king_zone = enemy_king.positions().fold(enemy_king, |acc, k| acc | movement::king_moves(k));
+
+        let mut mobility = Phased::default();
+        let mut danger = 0;
+        for kind in [PieceKind::Knight, PieceKind::Bishop, PieceKind::Rook, PieceKind::Queen] {
+            let idx = kind_index(kind);
+            for pos in (kind_bb(board, kind) & own).positions() {
+                let attacks = piece_attacks(kind, pos, world);
+                mobility += MOBILITY[idx] * i32::from((attacks & safe).count());
+                danger += KING_ATTACK_WEIGHTS[idx] * i32::from((attacks & king_zone).count());
+            }
+        }
+        (mobility, danger)
+    }
+
+    fn pawn_structure(board: &ChessBoard, team: Team) -> Phased {
+        let pawns = board.pawns & team_bb(board, team);
+        let enemy_pawns = board.pawns & team_bb(board, team.enemy());
+        let mut score = Phased::default();
+
+        for col in 0..8 {
+            let on_file = i32::from((pawns & Bitboard::from_col(col)).count());
+            if on_file > 1 {
+                score += DOUBLED_PAWN * (on_file - 1);
+            }
+
+            let neighbours = neighbouring_cols(col).where_not(Bitboard::from_col(col));
+            if on_file > 0 && (pawns & neighbours).empty() {
+                score += ISOLATED_PAWN * on_file;
+            }
+        }
+
+        for pos in pawns.positions() {
+            let span = neighbouring_cols(pos.col()) & rows_ahead(pos, team);
+            if (enemy_pawns & span).empty() {
+                score += PASSED_PAWN[relative_row(pos, team) as usize];
+            }
+        }
+        score
+    }
+
+    ///
+    /// Pawns sheltering the king, only relevant while there are pieces around to attack it
+    ///
+    fn pawn_shield(board: &ChessBoard, team: Team) -> i32 {
+        let own = team_bb(board, team);
+        let Some(king) = (board.kings & own).positions().next() else {
+            return 0;
+        };
+
+        let shield_rows = match team {
+            Team::White => (king.row() + 1..(king.row() + 3).min(8)).fold(Bitboard::default(), |acc, r| acc | Bitboard::from_row(r)),
+            Team::Black => (king.row().saturating_sub(2)..king.row()).fold(Bitboard::default(), |acc, r| acc | Bitboard::from_row(r)),
+        };
+        let shelter = board.pawns & own & shield_rows & neighbouring_cols(king.col());
+        PAWN_SHIELD_BONUS * i32::from(shelter.count().min(3))
+    }
+
+    fn evaluate_team(board: &ChessBoard, team: Team) -> Phased {
+        let (mobility, danger) = Self::activity(board, team);
+        let king_attack = (danger * danger / 4).min(MAX_KING_DANGER);
+
+        Self::material_and_placement(board, team)
+            + mobility
+            + Self::pawn_structure(board, team)
+            + phased(Self::pawn_shield(board, team) + king_attack, 0)
+    }
+}
+
+impl Evaluator for TaperedEvaluator {
+    fn evaluate(&self, board: &ChessBoard, side: Team) -> i32 {
+        let phase = KINDS
+            .iter()
+            .map(|&kind| PHASE_WEIGHTS[kind_index(kind)] * i32::from(kind_bb(board, kind).count()))
+            .sum::<i32>()
+            .min(MAX_PHASE);
+
+        let total = Self::evaluate_team(board, Team::White) - Self::evaluate_team(board, Team::Black);
+        let blended = (total.mg * phase + total.eg * (MAX_PHASE - phase)) / MAX_PHASE;
+
+        relative(blended, side)
+    }
+}
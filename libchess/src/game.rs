@@ -1,14 +1,16 @@
-use crate::board::ChessBoard;
+use crate::board::{ChessBoard, LegalityMasks};
+use crate::move_list::MoveList;
+use crate::packed_move::{PackedKind, PackedMove};
 use crate::piece::{
     GBishop, GBlack, GKing, GKnight, GPawn, GQueen, GRook, GWhite, PieceInfo, PieceKind,
     TPieceKind, TTeam, Team,
 };
+use crate::movement;
 use crate::precalc::masks;
-use crate::zobrist::ZobKeys;
-use crate::zobrist::ZobristHash;
+use crate::zobrist::{self, ZobKeys, ZobristHash};
 use positioning::Bitboard;
 use positioning::Position;
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Promotion {
     Queen,
     Rook,
@@ -16,6 +18,17 @@ pub enum Promotion {
     Knight,
 }
 
+impl Promotion {
+    pub const fn piece_kind(self) -> PieceKind {
+        match self {
+            Self::Queen => PieceKind::Queen,
+            Self::Rook => PieceKind::Rook,
+            Self::Bishop => PieceKind::Bishop,
+            Self::Knight => PieceKind::Knight,
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub struct RawMove {
     pub to: Position,
@@ -42,7 +55,7 @@ impl std::fmt::Debug for RawMove {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameMove {
     Promote { promotion: Promotion, mov: RawMove },
     CapturePromote { promotion: Promotion, mov: RawMove },
@@ -54,22 +67,163 @@ pub enum GameMove {
 }
 
 impl GameMove {
-    // pub fn smith(&self) -> String{
-    //     match self{
-    //         Self::Regular(m) => {
-    //
-    //         }
-    //     }
-    // }
+    ///
+    /// The square the moving piece starts on,
+    /// for castling this is the king's square
+    ///
+    pub const fn from_pos(&self) -> Position {
+        match self {
+            Self::Promote { mov, .. }
+            | Self::CapturePromote { mov, .. }
+            | Self::Enpassant(mov)
+            | Self::Regular(mov)
+            | Self::Capture(mov) => mov.from,
+            Self::CastleKingside(team) | Self::CastleQueenside(team) => match team {
+                Team::White => Position::new(0, 4),
+                Team::Black => Position::new(7, 4),
+            },
+        }
+    }
+
+    ///
+    /// The square the moving piece lands on,
+    /// for castling this is the king's destination
+    ///
+    pub const fn to_pos(&self) -> Position {
+        match self {
+            Self::Promote { mov, .. }
+            | Self::CapturePromote { mov, .. }
+            | Self::Enpassant(mov)
+            | Self::Regular(mov)
+            | Self::Capture(mov) => mov.to,
+            Self::CastleKingside(Team::White) => Position::new(0, 6),
+            Self::CastleKingside(Team::Black) => Position::new(7, 6),
+            Self::CastleQueenside(Team::White) => Position::new(0, 2),
+            Self::CastleQueenside(Team::Black) => Position::new(7, 2),
+        }
+    }
+
+    ///
+    /// The kind of piece being moved,
+    /// castling counts as a king move
+    ///
+    pub const fn piece_kind(&self) -> PieceKind {
+        match self {
+            Self::Promote { mov, .. }
+            | Self::CapturePromote { mov, .. }
+            | Self::Enpassant(mov)
+            | Self::Regular(mov)
+            | Self::Capture(mov) => mov.piece.kind,
+            Self::CastleKingside(_) | Self::CastleQueenside(_) => PieceKind::King,
+        }
+    }
+
+    pub const fn is_capture(&self) -> bool {
+        matches!(
+            self,
+            Self::Capture(_) | Self::CapturePromote { .. } | Self::Enpassant(_)
+        )
+    }
+
+    pub const fn promotion(&self) -> Option<Promotion> {
+        match self {
+            Self::Promote { promotion, .. } | Self::CapturePromote { promotion, .. } => {
+                Some(*promotion)
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    /// Encode the move in UCI long algebraic notation, i.e `e2e4` or `e7e8q`
+    ///
+    /// Castling is written as the king's two-square move
+    ///
+    pub fn to_uci(&self) -> String {
+        let mut ret = self.from_pos().as_alphanum().to_lowercase();
+        ret += &self.to_pos().as_alphanum().to_lowercase();
+
+        if let Some(promotion) = self.promotion() {
+            ret.push(match promotion {
+                Promotion::Queen => 'q',
+                Promotion::Rook => 'r',
+                Promotion::Bishop => 'b',
+                Promotion::Knight => 'n',
+            });
+        }
+        ret
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawReason {
+    /// The side to move has no legal moves, but is not in check
+    Stalemate,
+    /// 50 moves by each side without a capture or pawn move, claimed by a player
+    FiftyMoveRule,
+    /// 75 moves by each side without a capture or pawn move
+    SeventyFiveMoveRule,
+    /// The same position occurred three times, claimed by a player
+    ThreefoldRepetition,
+    /// The same position occurred five times
+    FivefoldRepetition,
+    /// Neither side has the material to deliver checkmate
+    InsufficientMaterial,
+    /// Both players agreed to a draw
+    Agreement,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum GameState {
+    #[default]
     WhiteToMove,
     BlackToMove,
     WhiteVictory,
     BlackVictory,
-    Stalemate,
+    Draw { reason: DrawReason, to_move: Team },
+}
+
+impl GameState {
+    pub const fn is_finished(&self) -> bool {
+        !matches!(self, Self::WhiteToMove | Self::BlackToMove)
+    }
+}
+
+///
+/// Where moves may land when every move is wanted
+///
+const EVERYWHERE: Bitboard = Bitboard::from_bits(u64::MAX);
+
+///
+/// Which of the legal moves of a position to generate
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MoveStage {
+    All,
+
+    ///
+    /// Captures, en passant and promotions
+    ///
+    Captures,
+
+    ///
+    /// Everything which is neither a capture nor a promotion, castling included
+    ///
+    Quiets,
+
+    ///
+    /// Quiet moves putting the enemy king in check
+    ///
+    QuietChecks,
+}
+
+impl MoveStage {
+    ///
+    /// Whether en passant and promotions are generated
+    ///
+    const fn is_tactical(self) -> bool {
+        matches!(self, Self::All | Self::Captures)
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -78,13 +232,41 @@ pub enum MoveGenerationError {
     GameFinished(GameState),
 }
 
-impl Default for GameState {
-    fn default() -> Self {
-        Self::WhiteToMove
-    }
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum UciMoveError {
+    #[error("Malformed UCI move '{0}'")]
+    Malformed(String),
+    #[error("Illegal move '{0}'")]
+    Illegal(String),
+    #[error("Game Finished")]
+    GameFinished(GameState),
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum FenParseError {
+    #[error("Expected 4 to 6 space-separated fields, found {0}")]
+    BadFieldCount(usize),
+    #[error("Expected 8 ranks in the piece placement, found {0}")]
+    BadRankCount(usize),
+    #[error("Rank {rank} does not describe exactly 8 squares")]
+    BadRankLength { rank: u8 },
+    #[error("Unknown piece letter '{0}'")]
+    UnknownPiece(char),
+    #[error("Expected exactly one {team:?} king, found {count}")]
+    BadKingCount { team: Team, count: u8 },
+    #[error("Invalid side to move '{0}', expected 'w' or 'b'")]
+    BadSideToMove(String),
+    #[error("Illegal castling token '{0}'")]
+    BadCastling(char),
+    #[error("Invalid en passant square '{0}'")]
+    BadEnPassant(String),
+    #[error("Invalid halfmove clock '{0}'")]
+    BadHalfmoveClock(String),
+    #[error("Invalid fullmove number '{0}'")]
+    BadFullmoveNumber(String),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 
 ///
 /// Castling Information
@@ -98,23 +280,43 @@ impl Default for GameState {
 pub struct CastleInfo(u8);
 
 impl CastleInfo {
-    pub fn unset_white_kingside(&mut self) {
+    ///
+    /// Castling information where neither side may castle
+    ///
+    pub const fn none() -> Self {
+        Self(0)
+    }
+
+    pub const fn set_white_kingside(&mut self) {
+        self.0 |= 0b1000;
+    }
+    pub const fn set_white_queenside(&mut self) {
+        self.0 |= 0b0100;
+    }
+    pub const fn set_black_kingside(&mut self) {
+        self.0 |= 0b0010;
+    }
+    pub const fn set_black_queenside(&mut self) {
+        self.0 |= 0b0001;
+    }
+
+    pub const fn unset_white_kingside(&mut self) {
         self.0 &= 0b0111;
     }
-    pub fn unset_white_queenside(&mut self) {
+    pub const fn unset_white_queenside(&mut self) {
         self.0 &= 0b1011;
     }
-    pub fn unset_black_kingside(&mut self) {
+    pub const fn unset_black_kingside(&mut self) {
         self.0 &= 0b1101;
     }
-    pub fn unset_black_queenside(&mut self) {
+    pub const fn unset_black_queenside(&mut self) {
         self.0 &= 0b1110;
     }
 
-    pub fn unset_white(&mut self) {
+    pub const fn unset_white(&mut self) {
         self.0 &= 0b0011;
     }
-    pub fn unset_black(&mut self) {
+    pub const fn unset_black(&mut self) {
         self.0 &= 0b1100;
     }
 
@@ -141,7 +343,19 @@ impl Default for CastleInfo {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+///
+/// Everything which must match for two positions
+/// to count as a repetition
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PositionKey {
+    board: ChessBoard,
+    castling: CastleInfo,
+    enpassant: Bitboard,
+    state: GameState,
+}
+
+#[derive(Debug, Clone)]
 pub struct Game {
     pub board: ChessBoard,
     pub halfmove_num: u64,
@@ -149,27 +363,141 @@ pub struct Game {
     pub enpassant: Bitboard,
     pub castling: CastleInfo,
     pub state: GameState,
+
+    ///
+    /// Positions reached since the last capture or pawn move,
+    /// including the current one
+    ///
+    history: Vec<PositionKey>,
+
+    ///
+    /// Zobrist hash of the current position,
+    /// kept up to date by `make_move` and `unmake_move`
+    ///
+    hash: ZobristHash,
+}
+
+///
+/// Everything needed to take back a move,
+/// as returned by `Game::make_move`
+///
+#[derive(Debug, Clone)]
+pub struct UndoInfo {
+    pub captured: Option<PieceInfo>,
+    pub castling: CastleInfo,
+    pub enpassant: Bitboard,
+    pub halfmove_num: u64,
+    pub fullmove_num: u64,
+    pub state: GameState,
+    pub hash: ZobristHash,
+
+    ///
+    /// The positions dropped from the history by an irreversible move
+    ///
+    history: Option<Vec<PositionKey>>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        let mut game = Self {
+            board: ChessBoard::default(),
+            halfmove_num: 0,
+            fullmove_num: 1,
+            enpassant: Bitboard::default(),
+            castling: CastleInfo::default(),
+            state: GameState::default(),
+            history: Vec::new(),
+            hash: ZobristHash::default(),
+        };
+        game.hash = game.get_zobrist_hash(zobrist::keys());
+        game.history.push(game.position_key());
+        game
+    }
 }
 
 impl Game {
 
-    // pub fn from_fen_str(fen : &str) -> Self{
-    //     let mut parts = fen.split(" ");
-    //     let board = parts.next().unwrap();
-    //     let side = parts.next().unwrap();
-    //     let castling = parts.next().unwrap();
-    //     let ep = parts.next().unwrap();
-    //     let halfmoves = parts.next().unwrap();
-    //     let fullmoves = parts.next().unwrap();
-    //
-    //     
-    // }
+    ///
+    /// Load a game from a FEN string
+    ///
+    /// The halfmove clock and fullmove number may be omitted,
+    /// in which case they default to 0 and 1 respectively
+    ///
+    pub fn from_fen_str(fen: &str) -> Result<Self, FenParseError> {
+        let parts: Vec<_> = fen.split_whitespace().collect();
+        if !(4..=6).contains(&parts.len()) {
+            return Err(FenParseError::BadFieldCount(parts.len()));
+        }
+
+        let board = ChessBoard::from_fen(parts[0])?;
+
+        let state = match parts[1] {
+            "w" => GameState::WhiteToMove,
+            "b" => GameState::BlackToMove,
+            side => return Err(FenParseError::BadSideToMove(side.to_owned())),
+        };
+
+        let mut castling = CastleInfo::none();
+        if parts[2] != "-" {
+            for token in parts[2].chars() {
+                match token {
+                    'K' if !castling.white_kingside() => castling.set_white_kingside(),
+                    'Q' if !castling.white_queenside() => castling.set_white_queenside(),
+                    'k' if !castling.black_kingside() => castling.set_black_kingside(),
+                    'q' if !castling.black_queenside() => castling.set_black_queenside(),
+                    _ => return Err(FenParseError::BadCastling(token)),
+                }
+            }
+        }
+
+        let enpassant = if parts[3] == "-" {
+            Bitboard::default()
+        } else {
+            // The target square sits behind a pawn that just double-pushed
+            let ep_row = match state {
+                GameState::WhiteToMove => 5,
+                _ => 2,
+            };
+            match Position::from_alphanum(parts[3]) {
+                Some(pos) if pos.row() == ep_row => Bitboard::from(pos),
+                _ => return Err(FenParseError::BadEnPassant(parts[3].to_owned())),
+            }
+        };
+
+        let halfmove_num = match parts.get(4) {
+            Some(hm) => hm
+                .parse()
+                .map_err(|_| FenParseError::BadHalfmoveClock((*hm).to_owned()))?,
+            None => 0,
+        };
+        let fullmove_num = match parts.get(5) {
+            Some(fm) => match fm.parse() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(FenParseError::BadFullmoveNumber((*fm).to_owned())),
+            },
+            None => 1,
+        };
+
+        let mut game = Self {
+            board,
+            halfmove_num,
+            fullmove_num,
+            enpassant,
+            castling,
+            state,
+            history: Vec::new(),
+            hash: ZobristHash::default(),
+        };
+        game.hash = game.get_zobrist_hash(zobrist::keys());
+        game.history.push(game.position_key());
+        Ok(game)
+    }
+
     pub fn to_fen_str(&self) -> String {
         let bs = self.board.to_fen();
-        let side = match self.state {
-            GameState::WhiteToMove => 'w',
-            GameState::BlackToMove => 'b',
-            _ => '-',
+        let side = match self.side_to_move() {
+            Team::White => 'w',
+            Team::Black => 'b',
         };
 
         let castling = {
@@ -211,7 +539,107 @@ impl Game {
         }
     }
 
-    pub fn get_zobrist_hash(&self, zkeys: &ZobKeys) -> Option<ZobristHash> {
+    ///
+    /// The team whose turn it is, even if the game has finished
+    ///
+    pub const fn side_to_move(&self) -> Team {
+        match self.state {
+            GameState::WhiteToMove | GameState::BlackVictory => Team::White,
+            GameState::BlackToMove | GameState::WhiteVictory => Team::Black,
+            GameState::Draw { to_move, .. } => to_move,
+        }
+    }
+
+    ///
+    /// The en passant square, if a pawn is actually able to capture onto it
+    ///
+    /// Only then does it distinguish otherwise identical positions
+    ///
+    fn capturable_enpassant(&self) -> Bitboard {
+        if self.enpassant.empty() {
+            return self.enpassant;
+        }
+        let ep_pos = self.enpassant.piece_position();
+        let capturers = match self.side_to_move() {
+            Team::White => movement::pawn_attackers::<GBlack>(ep_pos) & self.board.pieces::<GWhite, GPawn>(),
+            Team::Black => movement::pawn_attackers::<GWhite>(ep_pos) & self.board.pieces::<GBlack, GPawn>(),
+        };
+        if capturers.empty() {
+            Bitboard::default()
+        } else {
+            self.enpassant
+        }
+    }
+
+    fn position_key(&self) -> PositionKey {
+        PositionKey {
+            board: self.board,
+            castling: self.castling,
+            enpassant: self.capturable_enpassant(),
+            state: self.state,
+        }
+    }
+
+    ///
+    /// How many times the current position has occurred,
+    /// including this occurrence
+    ///
+    pub fn repetition_count(&self) -> usize {
+        let key = self.position_key();
+        self.history.iter().filter(|k| **k == key).count()
+    }
+
+    ///
+    /// The draw the side to move is entitled to claim, if any
+    ///
+    pub fn claimable_draw(&self) -> Option<DrawReason> {
+        if self.state.is_finished() {
+            None
+        } else if self.repetition_count() >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.halfmove_num >= 100 {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// End the game in a draw if the side to move may claim one
+    ///
+    pub fn claim_draw(&mut self) -> Option<DrawReason> {
+        let reason = self.claimable_draw()?;
+        self.end_in_draw(reason);
+        Some(reason)
+    }
+
+    ///
+    /// End the game in a draw by mutual agreement
+    ///
+    pub const fn agree_draw(&mut self) {
+        if !self.state.is_finished() {
+            self.end_in_draw(DrawReason::Agreement);
+        }
+    }
+
+    const fn end_in_draw(&mut self, reason: DrawReason) {
+        self.state = GameState::Draw {
+            reason,
+            to_move: self.side_to_move(),
+        };
+    }
+
+    ///
+    /// Zobrist hash of the current position, under the keys of `zobrist::keys`
+    ///
+    pub const fn hash(&self) -> ZobristHash {
+        self.hash
+    }
+
+    ///
+    /// Compute the zobrist hash of the position from scratch
+    ///
+    pub fn get_zobrist_hash(&self, zkeys: &ZobKeys) -> ZobristHash {
         let mut hash = ZobristHash::default();
 
         if self.castling.white_kingside() {
@@ -227,18 +655,13 @@ impl Game {
             hash.update(zkeys.castle_black_qs);
         }
 
-        if !self.enpassant.empty() {
-            let pos = self.enpassant.piece_position();
-            println!("ENPASSANT");
-            hash.update(zkeys.enpassant_keys_for(pos));
+        let enpassant = self.capturable_enpassant();
+        if !enpassant.empty() {
+            hash.update(zkeys.enpassant_keys_for(enpassant.piece_position()));
         }
 
-        match self.state {
-            GameState::BlackToMove => {
-                hash.update(zkeys.black_to_move);
-            }
-            GameState::WhiteToMove => {}
-            _ => return None,
+        if self.side_to_move() == Team::White {
+            hash.update(zkeys.white_to_move);
         }
 
         for i in 0..64 {
@@ -250,33 +673,95 @@ impl Game {
             }
         }
 
-        Some(hash)
+        hash
     }
 
-    fn check_checkmated<Target: TTeam>(&self) -> bool {
-        let target_king = match Target::TEAM {
-            Team::White => self.board.pieces::<GWhite, GKing>(),
-            Team::Black => self.board.pieces::<GBlack, GKing>(),
+    ///
+    /// Whether the team to move is currently in check
+    ///
+    pub fn is_in_check(&self) -> bool {
+        match self.side_to_move() {
+            Team::White => {
+                let king = self.board.pieces::<GWhite, GKing>().piece_position();
+                self.board.is_pos_attacked::<GBlack>(king)
+            }
+            Team::Black => {
+                let king = self.board.pieces::<GBlack, GKing>().piece_position();
+                self.board.is_pos_attacked::<GWhite>(king)
+            }
         }
-        .piece_position();
+    }
 
-        let check = match Target::TEAM {
-            Team::White => self.board.is_pos_attacked::<GBlack>(target_king),
-            Team::Black => self.board.is_pos_attacked::<GWhite>(target_king),
-        };
-        let king_moves = match Target::TEAM {
-            Team::White => self
-                .board
-                .get_piece_legal_moves::<GWhite, GKing>(target_king),
-            Team::Black => self
-                .board
-                .get_piece_legal_moves::<GBlack, GKing>(target_king),
-        };
+    ///
+    /// The enemy pieces giving check to the team to move
+    ///
+    pub fn checkers(&self) -> Bitboard {
+        match self.side_to_move() {
+            Team::White => self.board.checkers::<GWhite>(),
+            Team::Black => self.board.checkers::<GBlack>(),
+        }
+    }
+
+    fn piece_has_legal_moves<T: TTeam, K: TPieceKind>(&self, masks: &LegalityMasks) -> bool {
+        self.board
+            .pieces::<T, K>()
+            .positions()
+            .any(|pos| !self.board.get_piece_masked_moves::<T, K>(pos, masks).empty())
+    }
+
+    ///
+    /// Determine whether the team has at least one legal move
+    ///
+    /// This bails out at the first piece found to have a move,
+    /// which is far cheaper than generating the full move list
+    ///
+    fn has_legal_moves<T: TTeam>(&self) -> bool {
+        let masks = self.board.legality_masks::<T>();
+
+        // Castling is never the only legal move, as the king
+        // could always step onto the rook's destination instead
+        if self.piece_has_legal_moves::<T, GKing>(&masks)
+            || self.piece_has_legal_moves::<T, GPawn>(&masks)
+            || self.piece_has_legal_moves::<T, GKnight>(&masks)
+            || self.piece_has_legal_moves::<T, GBishop>(&masks)
+            || self.piece_has_legal_moves::<T, GRook>(&masks)
+            || self.piece_has_legal_moves::<T, GQueen>(&masks)
+        {
+            return true;
+        }
 
-        // We do not have to care for castling at all 
-        // this is because castling rules dictate that you cannot 
-        // castle when in check anyway
-        check && king_moves.empty()
+        // En passant captures are not covered by the pawn movement masks
+        if !self.enpassant.empty() {
+            let mut mb = MoveList::new();
+            for pos in self.board.pieces::<T, GPawn>().positions() {
+                self.get_pawn_moves::<T>(pos, &masks, MoveStage::All, EVERYWHERE, &mut mb);
+            }
+            return !mb.is_empty();
+        }
+        false
+    }
+
+    ///
+    /// Ends the game if the team to move has no legal moves,
+    /// as either checkmate or stalemate
+    ///
+    fn check_game_end<T: TTeam>(&mut self) {
+        if self.has_legal_moves::<T>() {
+            return;
+        }
+
+        let king = self.board.pieces::<T, GKing>().piece_position();
+        self.state = if !self.board.is_pos_attacked::<T::Enemy>(king) {
+            GameState::Draw {
+                reason: DrawReason::Stalemate,
+                to_move: T::TEAM,
+            }
+        } else {
+            match T::TEAM {
+                Team::White => GameState::BlackVictory,
+                Team::Black => GameState::WhiteVictory,
+            }
+        };
     }
 
     fn handle_cap_side_effects(
@@ -285,8 +770,6 @@ impl Game {
         _from: Position,
         to: Position,
         cap: PieceInfo,
-        #[cfg(feature = "zobrist")] _hash: &mut ZobristHash,
-        #[cfg(feature = "zobrist")] _zkeys: &ZobKeys,
     ) {
         use PieceKind::*;
         use Team::*;
@@ -307,74 +790,19 @@ impl Game {
 
         self.halfmove_num = 0;
     }
-    fn handle_move_side_effects(
-        &mut self,
-        piece: PieceInfo,
-        from: Position,
-        to: Position,
-        #[cfg(feature = "zobrist")] hash: &mut ZobristHash,
-        #[cfg(feature = "zobrist")] zkeys: &ZobKeys,
-    ) {
-        #[cfg(feature = "zobrist")]
-        let prev_ep = self.enpassant;
+    fn handle_move_side_effects(&mut self, piece: PieceInfo, from: Position, to: Position) {
         self.enpassant = Bitboard::default();
         match (piece.kind, piece.team) {
-            (PieceKind::King, Team::White) => {
-                #[cfg(feature = "zobrist")]
-                {
-                    if self.castling.white_kingside(){
-                        hash.update(zkeys.castle_white_ks);
-                    }
-                    if self.castling.white_queenside(){
-                        hash.update(zkeys.castle_white_qs);
-                    }
-                }
-                self.castling.unset_white();
-            }
-            (PieceKind::King, Team::Black) => {
-                #[cfg(feature = "zobrist")]
-                {
-                    if self.castling.black_kingside(){
-                        hash.update(zkeys.castle_black_ks);
-                    }
-                    if self.castling.black_queenside(){
-                        hash.update(zkeys.castle_black_qs);
-                    }
-                }
-                self.castling.unset_black();
-            }
+            (PieceKind::King, Team::White) => self.castling.unset_white(),
+            (PieceKind::King, Team::Black) => self.castling.unset_black(),
             (PieceKind::Rook, Team::White) => match from.integral() {
-                0 => {
-                    #[cfg(feature = "zobrist")]
-                    if self.castling.white_queenside(){
-                        hash.update(zkeys.castle_white_qs);
-                    }
-                    self.castling.unset_white_queenside();
-                },
-                7 => {
-                    #[cfg(feature = "zobrist")]
-                    if self.castling.white_kingside(){
-                        hash.update(zkeys.castle_white_ks);
-                    }
-                    self.castling.unset_white_kingside();
-                },
+                0 => self.castling.unset_white_queenside(),
+                7 => self.castling.unset_white_kingside(),
                 _ => {}
             },
             (PieceKind::Rook, Team::Black) => match from.integral() {
-                56 => {
-                    #[cfg(feature = "zobrist")]
-                    if self.castling.black_queenside(){
-                        hash.update(zkeys.castle_black_qs);
-                    }
-                    self.castling.unset_black_queenside();
-                },
-                63 => {
-                    #[cfg(feature = "zobrist")]
-                    if self.castling.black_kingside(){
-                        hash.update(zkeys.castle_black_ks);
-                    }
-                    self.castling.unset_black_kingside();
-                },
+                56 => self.castling.unset_black_queenside(),
+                63 => self.castling.unset_black_kingside(),
                 _ => {}
             },
             (PieceKind::Pawn, Team::White) => {
@@ -392,44 +820,76 @@ impl Game {
             (_, _) => {}
         }
 
-        match piece.team {
-            Team::White => {
-                if self.check_checkmated::<GBlack>() {
-                    self.state = GameState::WhiteVictory;
-                }
-            }
-            Team::Black => {
-                if self.check_checkmated::<GWhite>() {
-                    self.state = GameState::BlackVictory;
-                }
-            }
-        }
         match piece.kind {
             PieceKind::Pawn => self.halfmove_num = 0,
             _ => self.halfmove_num += 1,
         }
+    }
 
-        #[cfg(feature = "zobrist")]
-        {
-            if prev_ep.empty() && !self.enpassant.empty(){
-               hash.update(zkeys.enpassant_keys_for(to));
-            }
-            else if !prev_ep.empty() && self.enpassant.empty(){
-                hash.update(zkeys.enpassant_keys_for(prev_ep.piece_position()));
-            }
-            else if !prev_ep.empty() && !self.enpassant.empty(){
-               hash.update(zkeys.enpassant_keys_for(prev_ep.piece_position()));
-               hash.update(zkeys.enpassant_keys_for(to));
-            }
+    ///
+    /// Toggle the keys for the castling rights and en passant square,
+    /// which are hashed out before a move and back in after it
+    ///
+    fn hash_rights(&mut self, zkeys: &ZobKeys) {
+        if self.castling.white_kingside() {
+            self.hash.update(zkeys.castle_white_ks);
+        }
+        if self.castling.white_queenside() {
+            self.hash.update(zkeys.castle_white_qs);
+        }
+        if self.castling.black_kingside() {
+            self.hash.update(zkeys.castle_black_ks);
+        }
+        if self.castling.black_queenside() {
+            self.hash.update(zkeys.castle_black_qs);
+        }
+        let enpassant = self.capturable_enpassant();
+        if !enpassant.empty() {
+            self.hash.update(zkeys.enpassant_keys_for(enpassant.piece_position()));
         }
     }
 
-    pub fn make_move(
-        &mut self,
-        gmove: GameMove,
-        #[cfg(feature = "zobrist")] hash: &mut ZobristHash,
-        #[cfg(feature = "zobrist")] zkeys: &ZobKeys,
-    ) {
+    ///
+    /// Toggle a piece on a square in the hash
+    ///
+    fn hash_piece(&mut self, zkeys: &ZobKeys, piece: PieceInfo, pos: Position) {
+        self.hash.update(zkeys.piece_keys_for(piece.kind, piece.team).at_pos(pos));
+    }
+
+    ///
+    /// Play a move on a copy of the game,
+    /// for when only the resulting position is of interest
+    ///
+    pub fn after_move(&self, gmove: GameMove) -> Self {
+        let mut after = self.clone();
+        after.make_move(gmove);
+        after
+    }
+
+    ///
+    /// Play a move, returning the information needed
+    /// to take it back with `Game::unmake_move`
+    ///
+    /// The move is not checked against the game's result. Moving on from
+    /// an ended game resumes play from the adjudicated position, which is
+    /// how perft walks through drawn positions. Callers playing a game out
+    /// should stop once `state` is finished, as `Game::moves` does
+    ///
+    pub fn make_move(&mut self, gmove: GameMove) -> UndoInfo {
+        let mut undo = UndoInfo {
+            captured: None,
+            castling: self.castling,
+            enpassant: self.enpassant,
+            halfmove_num: self.halfmove_num,
+            fullmove_num: self.fullmove_num,
+            state: self.state,
+            hash: self.hash,
+            history: None,
+        };
+
+        let zkeys = zobrist::keys();
+        self.hash_rights(zkeys);
+
         match gmove {
             GameMove::Regular(mov) => {
                 debug_assert_eq!(
@@ -441,23 +901,10 @@ impl Game {
                 *self.board.get_mut_team_bb_rt(mov.piece.team) ^= switch;
                 *self.board.get_mut_piece_bb_rt(mov.piece.kind) ^= switch;
 
-                #[cfg(feature = "zobrist")]
-                {
-                    // Zobrist Update
-                    let zk = zkeys.piece_keys_for(mov.piece.kind, mov.piece.team);
+                self.hash_piece(zkeys, mov.piece, mov.from);
+                self.hash_piece(zkeys, mov.piece, mov.to);
 
-                    hash.update(zk.at_pos(mov.from));
-                    hash.update(zk.at_pos(mov.to));
-                }
-                self.handle_move_side_effects(
-                    mov.piece,
-                    mov.from,
-                    mov.to,
-                    #[cfg(feature = "zobrist")]
-                    hash,
-                    #[cfg(feature = "zobrist")]
-                    zkeys,
-                );
+                self.handle_move_side_effects(mov.piece, mov.from, mov.to);
             }
             GameMove::Capture(mov) => {
                 debug_assert!(
@@ -467,61 +914,29 @@ impl Game {
                 let switch = Bitboard::from(mov.from) | Bitboard::from(mov.to);
 
                 let cap_pi = self.board.get_piece_info(mov.to).unwrap();
+                undo.captured = Some(cap_pi);
 
-                #[cfg(feature = "zobrist")]
-                {
-                    // Zobrist Update
-                    let p_zk = zkeys.piece_keys_for(mov.piece.kind, mov.piece.team);
-                    let c_zk = zkeys.piece_keys_for(cap_pi.kind, cap_pi.team);
-
-                    hash.update(p_zk.at_pos(mov.from));
-                    hash.update(p_zk.at_pos(mov.to));
-                    hash.update(c_zk.at_pos(mov.to));
-                }
+                self.hash_piece(zkeys, mov.piece, mov.from);
+                self.hash_piece(zkeys, mov.piece, mov.to);
+                self.hash_piece(zkeys, cap_pi, mov.to);
 
                 self.mask(Bitboard::from(mov.to).negative());
                 *self.board.get_mut_team_bb_rt(mov.piece.team) ^= switch;
                 *self.board.get_mut_piece_bb_rt(mov.piece.kind) ^= switch;
 
-                self.handle_move_side_effects(
-                    mov.piece,
-                    mov.from,
-                    mov.to,
-                    #[cfg(feature = "zobrist")]
-                    hash,
-                    #[cfg(feature = "zobrist")]
-                    zkeys,
-                );
-                self.handle_cap_side_effects(
-                    mov.piece,
-                    mov.from,
-                    mov.to,
-                    cap_pi,
-                    #[cfg(feature = "zobrist")]
-                    hash,
-                    #[cfg(feature = "zobrist")]
-                    zkeys,
-                );
+                self.handle_move_side_effects(mov.piece, mov.from, mov.to);
+                self.handle_cap_side_effects(mov.piece, mov.from, mov.to, cap_pi);
             }
             GameMove::Promote { promotion, mov } => {
                 let switch = Bitboard::from(mov.from) | Bitboard::from(mov.to);
 
+                let promoted = PieceInfo {
+                    kind: promotion.piece_kind(),
+                    team: mov.piece.team,
+                };
+                self.hash_piece(zkeys, mov.piece, mov.from);
+                self.hash_piece(zkeys, promoted, mov.to);
 
-                #[cfg(feature = "zobrist")]
-                {
-                    // Zobrist Update
-                    let new_pk = match promotion {
-                        Promotion::Knight => PieceKind::Knight,
-                        Promotion::Bishop => PieceKind::Bishop,
-                        Promotion::Queen => PieceKind::Queen,
-                        Promotion::Rook => PieceKind::Rook,
-                    };
-                    let p_zk = zkeys.piece_keys_for(mov.piece.kind, mov.piece.team);
-                    let prom_zk = zkeys.piece_keys_for(new_pk, mov.piece.team);
-
-                    hash.update(p_zk.at_pos(mov.from));
-                    hash.update(prom_zk.at_pos(mov.to));
-                }
                 let pbb = match promotion {
                     Promotion::Rook => self.board.get_mut_piece_bb::<GRook>(),
                     Promotion::Queen => self.board.get_mut_piece_bb::<GQueen>(),
@@ -533,41 +948,20 @@ impl Game {
                 *self.board.get_mut_team_bb_rt(mov.piece.team) ^= switch;
                 self.mask(Bitboard::from(mov.from).negative());
 
-                self.handle_move_side_effects(
-                    mov.piece,
-                    mov.from,
-                    mov.to,
-                    #[cfg(feature = "zobrist")]
-                    hash,
-                    #[cfg(feature = "zobrist")]
-                    zkeys,
-                );
+                self.handle_move_side_effects(mov.piece, mov.from, mov.to);
             }
             GameMove::CapturePromote { promotion, mov } => {
                 let switch = Bitboard::from(mov.from) | Bitboard::from(mov.to);
                 let cap_pi = self.board.get_piece_info(mov.to).unwrap();
+                undo.captured = Some(cap_pi);
 
-
-                #[cfg(feature = "zobrist")]
-                {
-                    let new_pk = match promotion {
-                        Promotion::Knight => PieceKind::Knight,
-                        Promotion::Bishop => PieceKind::Bishop,
-                        Promotion::Queen => PieceKind::Queen,
-                        Promotion::Rook => PieceKind::Rook,
-                    };
-                    let cap_pi = self.board.get_piece_info(mov.to).unwrap();
-
-                    // Zobrist update
-                    let p_zk = zkeys.piece_keys_for(mov.piece.kind, mov.piece.team);
-                    let prom_zk = zkeys.piece_keys_for(new_pk, mov.piece.team);
-                    let cap_zk = zkeys.piece_keys_for(cap_pi.kind, cap_pi.team);
-
-                    hash.update(cap_zk.at_pos(mov.to));
-                    hash.update(p_zk.at_pos(mov.from));
-                    hash.update(prom_zk.at_pos(mov.to));
-                }
-                self.mask(Bitboard::from(mov.to).negative());
+                let promoted = PieceInfo {
+                    kind: promotion.piece_kind(),
+                    team: mov.piece.team,
+                };
+                self.hash_piece(zkeys, cap_pi, mov.to);
+                self.hash_piece(zkeys, mov.piece, mov.from);
+                self.hash_piece(zkeys, promoted, mov.to);
 
                 self.mask(Bitboard::from(mov.to).negative());
 
@@ -582,25 +976,8 @@ impl Game {
                 *pbb |= Bitboard::from(mov.to);
                 self.mask(Bitboard::from(mov.from).negative());
 
-                self.handle_move_side_effects(
-                    mov.piece,
-                    mov.from,
-                    mov.to,
-                    #[cfg(feature = "zobrist")]
-                    hash,
-                    #[cfg(feature = "zobrist")]
-                    zkeys,
-                );
-                self.handle_cap_side_effects(
-                    mov.piece,
-                    mov.from,
-                    mov.to,
-                    cap_pi,
-                    #[cfg(feature = "zobrist")]
-                    hash,
-                    #[cfg(feature = "zobrist")]
-                    zkeys,
-                );
+                self.handle_move_side_effects(mov.piece, mov.from, mov.to);
+                self.handle_cap_side_effects(mov.piece, mov.from, mov.to, cap_pi);
             }
             GameMove::Enpassant(mov) => {
                 let cap_pos = match mov.piece.team {
@@ -609,19 +986,11 @@ impl Game {
                 };
 
                 let cap_pi = self.board.get_piece_info(cap_pos.piece_position()).unwrap();
+                undo.captured = Some(cap_pi);
 
-                #[cfg(feature = "zobrist")]
-                {
-                    let cap_pi = self.board.get_piece_info(cap_pos.piece_position()).unwrap();
-
-                    // Zobrist Update
-                    let p_zk = zkeys.piece_keys_for(mov.piece.kind, mov.piece.team);
-                    let cap_zk = zkeys.piece_keys_for(cap_pi.kind, cap_pi.team);
-
-                    hash.update(p_zk.at_pos(mov.from));
-                    hash.update(p_zk.at_pos(mov.to));
-                    hash.update(cap_zk.at_pos(cap_pos.piece_position()));
-                }
+                self.hash_piece(zkeys, mov.piece, mov.from);
+                self.hash_piece(zkeys, mov.piece, mov.to);
+                self.hash_piece(zkeys, cap_pi, cap_pos.piece_position());
 
                 // Delete the captured piece
                 self.mask(cap_pos.negative());
@@ -633,25 +1002,8 @@ impl Game {
                 *self.board.get_mut_team_bb_rt(mov.piece.team) ^= switch;
                 *self.board.get_mut_piece_bb_rt(mov.piece.kind) ^= switch;
 
-                self.handle_move_side_effects(
-                    mov.piece,
-                    mov.from,
-                    mov.to,
-                    #[cfg(feature = "zobrist")]
-                    hash,
-                    #[cfg(feature = "zobrist")]
-                    zkeys,
-                );
-                self.handle_cap_side_effects(
-                    mov.piece,
-                    mov.from,
-                    mov.to,
-                    cap_pi,
-                    #[cfg(feature = "zobrist")]
-                    hash,
-                    #[cfg(feature = "zobrist")]
-                    zkeys,
-                );
+                self.handle_move_side_effects(mov.piece, mov.from, mov.to);
+                self.handle_cap_side_effects(mov.piece, mov.from, mov.to, cap_pi);
             }
             GameMove::CastleKingside(team) => {
                 use crate::precalc::masks::castling;
@@ -660,46 +1012,25 @@ impl Game {
                     Team::Black => (castling::B_KS_KING_SWITCH, castling::B_KS_ROOK_SWITCH),
                 };
 
-                let (king_from, king_to) = match team{
-                    Team::White => (Position::new(0, 4), Position::new(0, 6)),
-                    Team::Black => (Position::new(7, 4), Position::new(7, 6))
+                let row = match team {
+                    Team::White => 0,
+                    Team::Black => 7,
                 };
-                #[cfg(feature = "zobrist")]
-                {
-                    // Zobrist Update
-                    let k_zk = zkeys.piece_keys_for(PieceKind::King, team);
-                    let r_zk = zkeys.piece_keys_for(PieceKind::Rook, team);
-
-                    match team {
-                        Team::White => {
-                            hash.update(k_zk.at_pos(Position::new(0, 4)));
-                            hash.update(r_zk.at_pos(Position::new(0, 7)));
-                            hash.update(k_zk.at_pos(Position::new(0, 6)));
-                            hash.update(r_zk.at_pos(Position::new(0, 5)));
-                        }
-                        Team::Black => {
-                            hash.update(k_zk.at_pos(Position::new(7, 4)));
-                            hash.update(r_zk.at_pos(Position::new(7, 7)));
-                            hash.update(k_zk.at_pos(Position::new(7, 6)));
-                            hash.update(r_zk.at_pos(Position::new(7, 5)));
-                        }
-                    }
-                }
+                let (king_from, king_to) = (Position::new(row, 4), Position::new(row, 6));
+
+                let king = PieceInfo { team, kind: PieceKind::King };
+                let rook = PieceInfo { team, kind: PieceKind::Rook };
+                self.hash_piece(zkeys, king, king_from);
+                self.hash_piece(zkeys, king, king_to);
+                self.hash_piece(zkeys, rook, Position::new(row, 7));
+                self.hash_piece(zkeys, rook, Position::new(row, 5));
 
                 let team_switch = ks | rs;
                 *self.board.get_mut_team_bb_rt(team) ^= team_switch;
                 *self.board.get_mut_piece_bb::<GKing>() ^= ks;
                 *self.board.get_mut_piece_bb::<GRook>() ^= rs;
 
-                self.handle_move_side_effects(
-                    PieceInfo{team, kind: PieceKind::King},
-                    king_from,
-                    king_to,
-                    #[cfg(feature = "zobrist")]
-                    hash,
-                    #[cfg(feature = "zobrist")]
-                    zkeys,
-                );
+                self.handle_move_side_effects(king, king_from, king_to);
             }
             GameMove::CastleQueenside(team) => {
                 use crate::precalc::masks::castling;
@@ -708,67 +1039,150 @@ impl Game {
                     Team::Black => (castling::B_QS_KING_SWITCH, castling::B_QS_ROOK_SWITCH),
                 };
 
-                let (king_from, king_to) = match team{
-                    Team::White => (Position::new(0, 4), Position::new(0, 2)),
-                    Team::Black => (Position::new(7, 4), Position::new(7, 2))
+                let row = match team {
+                    Team::White => 0,
+                    Team::Black => 7,
                 };
+                let (king_from, king_to) = (Position::new(row, 4), Position::new(row, 2));
+
+                let king = PieceInfo { team, kind: PieceKind::King };
+                let rook = PieceInfo { team, kind: PieceKind::Rook };
+                self.hash_piece(zkeys, king, king_from);
+                self.hash_piece(zkeys, king, king_to);
+                self.hash_piece(zkeys, rook, Position::new(row, 0));
+                self.hash_piece(zkeys, rook, Position::new(row, 3));
 
-                #[cfg(feature = "zobrist")]
-                {
-                    // Zobrist Update
-                    let k_zk = zkeys.piece_keys_for(PieceKind::King, team);
-                    let r_zk = zkeys.piece_keys_for(PieceKind::Rook, team);
-
-                    match team {
-                        Team::White => {
-                            hash.update(k_zk.at_pos(Position::new(0, 4)));
-                            hash.update(r_zk.at_pos(Position::new(0, 0)));
-                            hash.update(k_zk.at_pos(Position::new(0, 2)));
-                            hash.update(r_zk.at_pos(Position::new(0, 3)));
-                        }
-                        Team::Black => {
-                            hash.update(k_zk.at_pos(Position::new(7, 4)));
-                            hash.update(r_zk.at_pos(Position::new(7, 0)));
-                            hash.update(k_zk.at_pos(Position::new(7, 2)));
-                            hash.update(r_zk.at_pos(Position::new(7, 3)));
-                        }
-                    }
-                }
                 let team_switch = ks | rs;
                 *self.board.get_mut_team_bb_rt(team) ^= team_switch;
                 *self.board.get_mut_piece_bb::<GKing>() ^= ks;
                 *self.board.get_mut_piece_bb::<GRook>() ^= rs;
 
+                self.handle_move_side_effects(king, king_from, king_to);
+            }
+        }
 
-                self.handle_move_side_effects(
-                    PieceInfo{team, kind: PieceKind::King},
-                    king_from,
-                    king_to,
-                    #[cfg(feature = "zobrist")]
-                    hash,
-                    #[cfg(feature = "zobrist")]
-                    zkeys,
-                );
+        match self.side_to_move() {
+            Team::White => self.state = GameState::BlackToMove,
+            Team::Black => {
+                self.state = GameState::WhiteToMove;
+                self.fullmove_num += 1;
             }
         }
 
+        // Whether en passant is hashed depends on the new side to move
+        self.hash_rights(zkeys);
+        self.hash.update(zkeys.white_to_move);
+
+        // No earlier position can recur after a capture or pawn move
+        if self.halfmove_num == 0 {
+            undo.history = Some(std::mem::take(&mut self.history));
+        }
+        self.history.push(self.position_key());
+
         match self.state {
-            GameState::WhiteToMove => {
-                self.state = GameState::BlackToMove;
-                #[cfg(feature = "zobrist")]
-                hash.update(zkeys.black_to_move);
+            GameState::WhiteToMove => self.check_game_end::<GWhite>(),
+            GameState::BlackToMove => self.check_game_end::<GBlack>(),
+            _ => {}
+        }
+        self.check_automatic_draw();
+
+        undo
+    }
+
+    ///
+    /// Take back the last move played,
+    /// restoring the game to exactly its prior state
+    ///
+    pub fn unmake_move(&mut self, gmove: GameMove, undo: UndoInfo) {
+        let restore = |board: &mut ChessBoard, pos: Position| {
+            let pi = undo.captured.expect("Capturing moves record the captured piece");
+            let bb = Bitboard::from(pos);
+            *board.get_mut_team_bb_rt(pi.team) |= bb;
+            *board.get_mut_piece_bb_rt(pi.kind) |= bb;
+        };
+
+        match gmove {
+            GameMove::Regular(mov) => {
+                let switch = Bitboard::from(mov.from) | Bitboard::from(mov.to);
+                *self.board.get_mut_team_bb_rt(mov.piece.team) ^= switch;
+                *self.board.get_mut_piece_bb_rt(mov.piece.kind) ^= switch;
             }
-            GameState::BlackToMove => {
-                self.state = GameState::WhiteToMove;
-                #[cfg(feature = "zobrist")]
-                hash.update(zkeys.black_to_move);
+            GameMove::Capture(mov) => {
+                let switch = Bitboard::from(mov.from) | Bitboard::from(mov.to);
+                *self.board.get_mut_team_bb_rt(mov.piece.team) ^= switch;
+                *self.board.get_mut_piece_bb_rt(mov.piece.kind) ^= switch;
+                restore(&mut self.board, mov.to);
             }
-            _ => {}
+            GameMove::Enpassant(mov) => {
+                let switch = Bitboard::from(mov.from) | Bitboard::from(mov.to);
+                *self.board.get_mut_team_bb_rt(mov.piece.team) ^= switch;
+                *self.board.get_mut_piece_bb_rt(mov.piece.kind) ^= switch;
+
+                // The captured pawn sits beside the capturing pawn's origin
+                restore(&mut self.board, Position::new(mov.from.row(), mov.to.col()));
+            }
+            GameMove::Promote { mov, .. } | GameMove::CapturePromote { mov, .. } => {
+                self.mask(Bitboard::from(mov.to).negative());
+
+                let from = Bitboard::from(mov.from);
+                *self.board.get_mut_team_bb_rt(mov.piece.team) |= from;
+                *self.board.get_mut_piece_bb_rt(mov.piece.kind) |= from;
+
+                if let GameMove::CapturePromote { .. } = gmove {
+                    restore(&mut self.board, mov.to);
+                }
+            }
+            GameMove::CastleKingside(team) => {
+                use crate::precalc::masks::castling;
+                let (ks, rs) = match team {
+                    Team::White => (castling::W_KS_KING_SWITCH, castling::W_KS_ROOK_SWITCH),
+                    Team::Black => (castling::B_KS_KING_SWITCH, castling::B_KS_ROOK_SWITCH),
+                };
+                *self.board.get_mut_team_bb_rt(team) ^= ks | rs;
+                *self.board.get_mut_piece_bb::<GKing>() ^= ks;
+                *self.board.get_mut_piece_bb::<GRook>() ^= rs;
+            }
+            GameMove::CastleQueenside(team) => {
+                use crate::precalc::masks::castling;
+                let (ks, rs) = match team {
+                    Team::White => (castling::W_QS_KING_SWITCH, castling::W_QS_ROOK_SWITCH),
+                    Team::Black => (castling::B_QS_KING_SWITCH, castling::B_QS_ROOK_SWITCH),
+                };
+                *self.board.get_mut_team_bb_rt(team) ^= ks | rs;
+                *self.board.get_mut_piece_bb::<GKing>() ^= ks;
+                *self.board.get_mut_piece_bb::<GRook>() ^= rs;
+            }
+        }
+
+        self.castling = undo.castling;
+        self.enpassant = undo.enpassant;
+        self.halfmove_num = undo.halfmove_num;
+        self.fullmove_num = undo.fullmove_num;
+        self.state = undo.state;
+        self.hash = undo.hash;
+        match undo.history {
+            Some(history) => self.history = history,
+            None => {
+                self.history.pop();
+            }
+        }
+    }
+
+    ///
+    /// Ends the game if it is drawn without either player
+    /// needing to claim it
+    ///
+    fn check_automatic_draw(&mut self) {
+        if self.state.is_finished() {
+            return;
         }
 
-        // 50 full moves have been made with no "progress"
-        if self.halfmove_num == 100 {
-            self.state = GameState::Stalemate;
+        if self.board.has_insufficient_material() {
+            self.end_in_draw(DrawReason::InsufficientMaterial);
+        } else if self.repetition_count() >= 5 {
+            self.end_in_draw(DrawReason::FivefoldRepetition);
+        } else if self.halfmove_num >= 150 {
+            self.end_in_draw(DrawReason::SeventyFiveMoveRule);
         }
     }
 
@@ -784,16 +1198,47 @@ impl Game {
         self.board.blacks &= mask;
     }
 
-    fn get_king_moves<'m, T: TTeam>(
+    ///
+    /// Whether the team may castle kingside and queenside,
+    /// going by their rights and the squares needing to be clear
+    /// but not whether the king passes through check
+    ///
+    fn castles_unobstructed<T: TTeam>(&self) -> (bool, bool) {
+        let world = self.board.whites | self.board.blacks;
+        let (castle_ks, castle_qs, ks_clears, qs_clears) = if T::TEAM == Team::White {
+            (
+                self.castling.white_kingside(),
+                self.castling.white_queenside(),
+                Bitboard::W_KINGSIDE_CLEARS,
+                Bitboard::W_QUEENSIDE_CLEARS,
+            )
+        } else {
+            (
+                self.castling.black_kingside(),
+                self.castling.black_queenside(),
+                Bitboard::B_KINGSIDE_CLEARS,
+                Bitboard::B_QUEENSIDE_CLEARS,
+            )
+        };
+        (
+            castle_ks && (world & ks_clears).empty(),
+            castle_qs && (world & qs_clears).empty(),
+        )
+    }
+
+    fn get_king_moves<T: TTeam>(
         &self,
         piece: Position,
-        move_buf: &'m mut Vec<GameMove>,
-    ) -> &'m Vec<GameMove> {
+        masks: &LegalityMasks,
+        stage: MoveStage,
+        targets: Bitboard,
+        move_buf: &mut MoveList,
+    ) {
         let info = PieceInfo {
             kind: PieceKind::King,
             team: T::TEAM,
         };
-        let legals = self.board.get_piece_legal_moves::<T, GKing>(piece);
+        let legals = self.board.get_piece_masked_moves::<T, GKing>(piece, masks) & targets;
         let enemies = self.board.team_pieces::<T::Enemy>();
         let captures = legals & enemies;
         let noncaptures = legals & enemies.negative();
@@ -807,71 +1252,62 @@ impl Game {
             move_buf.push(GameMove::Regular(RawMove::new(piece, noncap_pos, info)));
         }
 
-        let world = self.board.whites | self.board.blacks;
+        if stage == MoveStage::Captures {
+            return;
+        }
 
-        // Determine Castling Ability
-        let (castle_ks, castle_qs) = if T::TEAM == Team::White {
-            (
-                self.castling.white_kingside(),
-                self.castling.white_queenside(),
-            )
-        } else {
-            (
-                self.castling.black_kingside(),
-                self.castling.black_queenside(),
-            )
-        };
+        let (castle_ks, castle_qs) = self.castles_unobstructed::<T>();
 
-        // Required safe and clear squares in order to castle
-        let (ks_clears, qs_clears, ks_safes, qs_safes) = if T::TEAM == Team::White {
-            (
-                Bitboard::W_KINGSIDE_CLEARS,
-                Bitboard::W_QUEENSIDE_CLEARS,
-                Bitboard::W_KINGSIDE_SAFES,
-                Bitboard::W_QUEENSIDE_SAFES,
-            )
+        // Squares the king must not be attacked on in order to castle
+        let (ks_safes, qs_safes) = if T::TEAM == Team::White {
+            (Bitboard::W_KINGSIDE_SAFES, Bitboard::W_QUEENSIDE_SAFES)
         } else {
-            (
-                Bitboard::B_KINGSIDE_CLEARS,
-                Bitboard::B_QUEENSIDE_CLEARS,
-                Bitboard::B_KINGSIDE_SAFES,
-                Bitboard::B_QUEENSIDE_SAFES,
-            )
+            (Bitboard::B_KINGSIDE_SAFES, Bitboard::B_QUEENSIDE_SAFES)
         };
 
-        if castle_ks && (world & ks_clears).empty() {
-            // Make sure we are not under attack
-            let attacked = ks_safes
-                .positions()
-                .any(|pos| self.board.is_pos_attacked::<T::Enemy>(pos));
-
-            if !attacked {
-                move_buf.push(GameMove::CastleKingside(T::TEAM));
+        let castles = [
+            (castle_ks && (ks_safes & masks.king_danger).empty(), GameMove::CastleKingside(T::TEAM)),
+            (castle_qs && (qs_safes & masks.king_danger).empty(), GameMove::CastleQueenside(T::TEAM)),
+        ];
+        for (allowed, castle) in castles {
+            if allowed && (stage != MoveStage::QuietChecks || self.castle_gives_check::<T>(castle)) {
+                move_buf.push(castle);
             }
         }
-        if castle_qs && world.where_also(qs_clears).empty() {
-            // Make sure we are not under attack
-            let attacked = qs_safes
-                .positions()
-                .any(|pos| self.board.is_pos_attacked::<T::Enemy>(pos));
+    }
 
-            if !attacked {
-                move_buf.push(GameMove::CastleQueenside(T::TEAM));
-            }
-        }
+    ///
+    /// Whether castling puts the enemy king in check,
+    /// as it can through the rook's new square
+    ///
+    fn castle_gives_check<T: TTeam>(&self, castle: GameMove) -> bool {
+        use crate::precalc::masks::castling;
+        let (ks, rs) = match (castle, T::TEAM) {
+            (GameMove::CastleKingside(_), Team::White) => (castling::W_KS_KING_SWITCH, castling::W_KS_ROOK_SWITCH),
+            (GameMove::CastleKingside(_), Team::Black) => (castling::B_KS_KING_SWITCH, castling::B_KS_ROOK_SWITCH),
+            (_, Team::White) => (castling::W_QS_KING_SWITCH, castling::W_QS_ROOK_SWITCH),
+            (_, Team::Black) => (castling::B_QS_KING_SWITCH, castling::B_QS_ROOK_SWITCH),
+        };
 
-        move_buf
+        let mut after = self.board;
+        *after.get_mut_team_bb::<T>() ^= ks | rs;
+        after.kings ^= ks;
+        after.rooks ^= rs;
+        let enemy_king = after.pieces::<T::Enemy, GKing>().piece_position();
+        after.is_pos_attacked::<T>(enemy_king)
     }
-    fn get_queen_moves<'m, T: TTeam>(
+    fn get_queen_moves<T: TTeam>(
         &self,
         piece: Position,
-        move_buf: &'m mut Vec<GameMove>,
-    ) -> &'m Vec<GameMove> {
+        masks: &LegalityMasks,
+        targets: Bitboard,
+        move_buf: &mut MoveList,
+    ) {
         let info = PieceInfo {
             team: T::TEAM,
             kind: PieceKind::Queen,
         };
-        let legals = self.board.get_piece_legal_moves::<T, GQueen>(piece);
+        let legals = self.board.get_piece_masked_moves::<T, GQueen>(piece, masks) & targets;
         let enemies = self.board.team_pieces::<T::Enemy>();
         let captures = legals & enemies;
         let noncaptures = legals & enemies.negative();
@@ -883,20 +1319,20 @@ impl Game {
         for noncap_pos in noncaptures.positions() {
             move_buf.push(GameMove::Regular(RawMove::new(piece, noncap_pos, info)));
         }
-
-        move_buf
     }
 
-    fn get_rook_moves<'m, T: TTeam>(
+    fn get_rook_moves<T: TTeam>(
         &self,
         piece: Position,
-        move_buf: &'m mut Vec<GameMove>,
-    ) -> &'m Vec<GameMove> {
+        masks: &LegalityMasks,
+        targets: Bitboard,
+        move_buf: &mut MoveList,
+    ) {
         let info = PieceInfo {
             team: T::TEAM,
             kind: PieceKind::Rook,
         };
-        let legals = self.board.get_piece_legal_moves::<T, GRook>(piece);
+        let legals = self.board.get_piece_masked_moves::<T, GRook>(piece, masks) & targets;
         let enemies = self.board.team_pieces::<T::Enemy>();
         let captures = legals & enemies;
         let noncaptures = legals & enemies.negative();
@@ -908,19 +1344,19 @@ impl Game {
         for noncap_pos in noncaptures.positions() {
             move_buf.push(GameMove::Regular(RawMove::new(piece, noncap_pos, info)));
         }
-
-        move_buf
     }
-    fn get_knight_moves<'m, T: TTeam>(
+    fn get_knight_moves<T: TTeam>(
         &self,
         piece: Position,
-        move_buf: &'m mut Vec<GameMove>,
-    ) -> &'m Vec<GameMove> {
+        masks: &LegalityMasks,
+        targets: Bitboard,
+        move_buf: &mut MoveList,
+    ) {
         let info = PieceInfo {
             team: T::TEAM,
             kind: PieceKind::Knight,
         };
-        let legals = self.board.get_piece_legal_moves::<T, GKnight>(piece);
+        let legals = self.board.get_piece_masked_moves::<T, GKnight>(piece, masks) & targets;
         let enemies = self.board.team_pieces::<T::Enemy>();
         let captures = legals & enemies;
         let noncaptures = legals & enemies.negative();
@@ -932,19 +1368,19 @@ impl Game {
         for noncap_pos in noncaptures.positions() {
             move_buf.push(GameMove::Regular(RawMove::new(piece, noncap_pos, info)));
         }
-
-        move_buf
     }
-    fn get_bishop_moves<'m, T: TTeam>(
+    fn get_bishop_moves<T: TTeam>(
         &self,
         piece: Position,
-        move_buf: &'m mut Vec<GameMove>,
-    ) -> &'m Vec<GameMove> {
+        masks: &LegalityMasks,
+        targets: Bitboard,
+        move_buf: &mut MoveList,
+    ) {
         let info = PieceInfo {
             team: T::TEAM,
             kind: PieceKind::Bishop,
         };
-        let legals = self.board.get_piece_legal_moves::<T, GBishop>(piece);
+        let legals = self.board.get_piece_masked_moves::<T, GBishop>(piece, masks) & targets;
         let enemies = self.board.team_pieces::<T::Enemy>();
 
         let captures = legals & enemies;
@@ -957,19 +1393,20 @@ impl Game {
         for noncap_pos in noncaptures.positions() {
             move_buf.push(GameMove::Regular(RawMove::new(piece, noncap_pos, info)));
         }
-
-        move_buf
     }
-    fn get_pawn_moves<'m, T: TTeam>(
+    fn get_pawn_moves<T: TTeam>(
         &self,
         piece: Position,
-        move_buf: &'m mut Vec<GameMove>,
-    ) -> &'m Vec<GameMove> {
+        masks: &LegalityMasks,
+        stage: MoveStage,
+        targets: Bitboard,
+        move_buf: &mut MoveList,
+    ) {
         let info = PieceInfo {
             team: T::TEAM,
             kind: PieceKind::Pawn,
         };
-        let legals = self.board.get_piece_legal_moves::<T, GPawn>(piece);
+        let legals = self.board.get_piece_masked_moves::<T, GPawn>(piece, masks);
         let enemies = self.board.team_pieces::<T::Enemy>();
         let promotion_row = if T::TEAM == Team::White {
             Bitboard::ROW_8
@@ -987,7 +1424,7 @@ impl Game {
         // We can use a few facts about enpassant for optimization 
         // If the captured pawn is white, they will be on row 4, and the ep will be to row 3
         // if the captured pawn is black, they will be on row 5, and the ep will be to row 6
-        if !(*pawn_attacks & self.enpassant).empty() {
+        if stage.is_tactical() && !(*pawn_attacks & self.enpassant).empty() {
             let ep_idx = self.enpassant.piece_position();
 
             let (captured_row, enpassant_row) = match T::Enemy::TEAM{
@@ -1039,10 +1476,16 @@ impl Game {
             }
         }
 
-        let captures = legals & enemies;
-        let noncaptures = legals & enemies.negative();
-        let cap_promotes = captures & promotion_row;
-        let reg_promotes = noncaptures & promotion_row;
+        // Reaching the last row is only ever a promotion
+        let captures = legals & targets & enemies & promotion_row.negative();
+        let noncaptures = legals & targets & enemies.negative() & promotion_row.negative();
+        let promotes = if stage.is_tactical() {
+            legals & promotion_row
+        } else {
+            Bitboard::default()
+        };
+        let cap_promotes = promotes & enemies;
+        let reg_promotes = promotes & enemies.negative();
 
         for cap in captures.positions() {
             move_buf.push(GameMove::Capture(RawMove {
@@ -1104,8 +1547,6 @@ impl Game {
                 promotion: Promotion::Knight,
             });
         }
-
-        move_buf
     }
 
     pub fn get_piece_moves<'m, T: TTeam, K: TPieceKind>(
@@ -1113,30 +1554,251 @@ impl Game {
         piece: Position,
         move_buf: &'m mut Vec<GameMove>,
     ) -> &'m Vec<GameMove> {
+        let masks = self.board.legality_masks::<T>();
+        let mut moves = MoveList::new();
         match K::KIND {
-            PieceKind::King => self.get_king_moves::<T>(piece, move_buf),
-            PieceKind::Queen => self.get_queen_moves::<T>(piece, move_buf),
-            PieceKind::Rook => self.get_rook_moves::<T>(piece, move_buf),
-            PieceKind::Knight => self.get_knight_moves::<T>(piece, move_buf),
-            PieceKind::Bishop => self.get_bishop_moves::<T>(piece, move_buf),
-            PieceKind::Pawn => self.get_pawn_moves::<T>(piece, move_buf),
+            PieceKind::King => self.get_king_moves::<T>(piece, &masks, MoveStage::All, EVERYWHERE, &mut moves),
+            PieceKind::Queen => self.get_queen_moves::<T>(piece, &masks, EVERYWHERE, &mut moves),
+            PieceKind::Rook => self.get_rook_moves::<T>(piece, &masks, EVERYWHERE, &mut moves),
+            PieceKind::Knight => self.get_knight_moves::<T>(piece, &masks, EVERYWHERE, &mut moves),
+            PieceKind::Bishop => self.get_bishop_moves::<T>(piece, &masks, EVERYWHERE, &mut moves),
+            PieceKind::Pawn => self.get_pawn_moves::<T>(piece, &masks, MoveStage::All, EVERYWHERE, &mut moves),
         }
+        move_buf.extend_from_slice(&moves);
+        move_buf
+    }
+
+    ///
+    /// Resolve a move in UCI long algebraic notation
+    /// against the legal moves of the current position
+    ///
+    pub fn parse_uci_move(&self, uci: &str) -> Result<GameMove, UciMoveError> {
+        let malformed = || UciMoveError::Malformed(uci.to_owned());
+
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return Err(malformed());
+        }
+        let from = Position::from_alphanum(&uci[0..2]).ok_or_else(malformed)?;
+        let to = Position::from_alphanum(&uci[2..4]).ok_or_else(malformed)?;
+        let promotion = match uci.as_bytes().get(4) {
+            None => None,
+            Some(b'q') => Some(Promotion::Queen),
+            Some(b'r') => Some(Promotion::Rook),
+            Some(b'b') => Some(Promotion::Bishop),
+            Some(b'n') => Some(Promotion::Knight),
+            Some(_) => return Err(malformed()),
+        };
+
+        let moves = self
+            .moves()
+            .map_err(|MoveGenerationError::GameFinished(state)| UciMoveError::GameFinished(state))?;
+
+        moves
+            .iter()
+            .find(|m| m.from_pos() == from && m.to_pos() == to && m.promotion() == promotion)
+            .copied()
+            .ok_or_else(|| UciMoveError::Illegal(uci.to_owned()))
+    }
+
+    ///
+    /// Rebuild a packed move in the current position,
+    /// or `None` if it cannot be a move of the side to move
+    ///
+    /// Whether the move is possible is not checked, see `Game::is_legal`
+    ///
+    pub fn unpack_move(&self, packed: PackedMove) -> Option<GameMove> {
+        let team = self.side_to_move();
+        let (from, to) = (packed.from_pos(), packed.to_pos());
+        let piece = self.board.get_piece_info(from).filter(|p| p.team == team)?;
+        let captured = self.board.get_piece_info(to);
+        if captured.is_some_and(|c| c.team == team) {
+            return None;
+        }
+        let mov = RawMove::new(from, to, piece);
+
+        let gmove = match (packed.kind(), captured) {
+            (PackedKind::Normal, None) => GameMove::Regular(mov),
+            (PackedKind::Normal, Some(_)) => GameMove::Capture(mov),
+            (PackedKind::Promotion, None) => GameMove::Promote {
+                promotion: packed.promotion()?,
+                mov,
+            },
+            (PackedKind::Promotion, Some(_)) => GameMove::CapturePromote {
+                promotion: packed.promotion()?,
+                mov,
+            },
+            (PackedKind::Enpassant, None) => GameMove::Enpassant(mov),
+            (PackedKind::Castle, None) => [GameMove::CastleKingside(team), GameMove::CastleQueenside(team)]
+                .into_iter()
+                .find(|c| c.from_pos() == from && c.to_pos() == to && piece.kind == PieceKind::King)?,
+            (PackedKind::Enpassant | PackedKind::Castle, Some(_)) => return None,
+        };
+        Some(gmove)
+    }
+
+    ///
+    /// Whether a packed move follows the movement rules for the side to move,
+    /// although it may leave their king in check
+    ///
+    pub fn is_pseudo_legal(&self, packed: PackedMove) -> bool {
+        self.unpack_move(packed).is_some_and(|gmove| match self.side_to_move() {
+            Team::White => self.static_is_pseudo_legal::<GWhite>(gmove),
+            Team::Black => self.static_is_pseudo_legal::<GBlack>(gmove),
+        })
+    }
+
+    fn static_is_pseudo_legal<T: TTeam>(&self, gmove: GameMove) -> bool {
+        let (castle_ks, castle_qs) = self.castles_unobstructed::<T>();
+        match gmove {
+            GameMove::CastleKingside(_) => castle_ks,
+            GameMove::CastleQueenside(_) => castle_qs,
+            GameMove::Enpassant(mov) => {
+                mov.piece.kind == PieceKind::Pawn
+                    && Bitboard::from(mov.to) == self.enpassant
+                    && !(movement::pawn_attackers::<T>(mov.from) & self.enpassant).empty()
+            }
+            GameMove::Regular(mov)
+            | GameMove::Capture(mov)
+            | GameMove::Promote { mov, .. }
+            | GameMove::CapturePromote { mov, .. } => {
+                let targets = match mov.piece.kind {
+                    PieceKind::King => self.board.get_piece_pseudo_moves::<T, GKing>(mov.from),
+                    PieceKind::Queen => self.board.get_piece_pseudo_moves::<T, GQueen>(mov.from),
+                    PieceKind::Rook => self.board.get_piece_pseudo_moves::<T, GRook>(mov.from),
+                    PieceKind::Knight => self.board.get_piece_pseudo_moves::<T, GKnight>(mov.from),
+                    PieceKind::Bishop => self.board.get_piece_pseudo_moves::<T, GBishop>(mov.from),
+                    PieceKind::Pawn => self.board.get_piece_pseudo_moves::<T, GPawn>(mov.from),
+                };
+                let promotion_row = match T::TEAM {
+                    Team::White => Bitboard::ROW_8,
+                    Team::Black => Bitboard::ROW_1,
+                };
+
+                // Pawns reaching the last row must promote, and nothing else may
+                let must_promote =
+                    mov.piece.kind == PieceKind::Pawn && !(promotion_row & Bitboard::from(mov.to)).empty();
+                !(targets & Bitboard::from(mov.to)).empty() && gmove.promotion().is_some() == must_promote
+            }
+        }
+    }
+
+    ///
+    /// Whether a packed move is a legal move of the side to move,
+    /// disregarding whether the game has been adjudicated
+    ///
+    /// Moves fetched from a table may belong to another position
+    /// altogether, so should be checked with this before being played
+    ///
+    pub fn is_legal(&self, packed: PackedMove) -> bool {
+        self.unpack_move(packed).is_some_and(|gmove| match self.side_to_move() {
+            Team::White => self.static_is_legal::<GWhite>(gmove),
+            Team::Black => self.static_is_legal::<GBlack>(gmove),
+        })
+    }
+
+    fn static_is_legal<T: TTeam>(&self, gmove: GameMove) -> bool {
+        // Only the moves of the piece in question need generating
+        let masks = self.board.legality_masks::<T>();
+        let mut moves = MoveList::new();
+        let from = gmove.from_pos();
+        match gmove.piece_kind() {
+            PieceKind::King => self.get_king_moves::<T>(from, &masks, MoveStage::All, EVERYWHERE, &mut moves),
+            PieceKind::Queen => self.get_queen_moves::<T>(from, &masks, EVERYWHERE, &mut moves),
+            PieceKind::Rook => self.get_rook_moves::<T>(from, &masks, EVERYWHERE, &mut moves),
+            PieceKind::Knight => self.get_knight_moves::<T>(from, &masks, EVERYWHERE, &mut moves),
+            PieceKind::Bishop => self.get_bishop_moves::<T>(from, &masks, EVERYWHERE, &mut moves),
+            PieceKind::Pawn => self.get_pawn_moves::<T>(from, &masks, MoveStage::All, EVERYWHERE, &mut moves),
+        }
+        moves.contains(&gmove)
     }
 
     pub fn get_all_moves<'m>(
         &self,
         move_buf: &'m mut Vec<GameMove>,
     ) -> Result<&'m [GameMove], MoveGenerationError> {
+        move_buf.extend_from_slice(&self.moves()?);
+        Ok(move_buf)
+    }
+
+    ///
+    /// Generate the moves of the side to move,
+    /// failing if the game has already finished
+    ///
+    pub fn moves(&self) -> Result<MoveList, MoveGenerationError> {
         match self.get_active_team() {
-            Some(Team::White) => Ok(self.static_get_all_moves::<GWhite>(move_buf)),
-            Some(Team::Black) => Ok(self.static_get_all_moves::<GBlack>(move_buf)),
+            Some(Team::White) => Ok(self.static_get_moves::<GWhite>(MoveStage::All)),
+            Some(Team::Black) => Ok(self.static_get_moves::<GBlack>(MoveStage::All)),
             None => Err(MoveGenerationError::GameFinished(self.state)),
         }
     }
-    fn static_get_all_moves<'m, T: TTeam>(
-        &self,
-        move_buf: &'m mut Vec<GameMove>,
-    ) -> &'m [GameMove] {
+
+    ///
+    /// Generate the legal moves of the side to move,
+    /// disregarding whether the game has been adjudicated
+    ///
+    /// This is what move generation tests such as perft want,
+    /// as they must keep counting through drawn positions
+    ///
+    pub fn get_legal_moves<'m>(&self, move_buf: &'m mut Vec<GameMove>) -> &'m [GameMove] {
+        move_buf.extend_from_slice(&self.legal_moves());
+        move_buf
+    }
+
+    ///
+    /// Generate the legal moves of the side to move into a list on the stack,
+    /// disregarding whether the game has been adjudicated
+    ///
+    pub fn legal_moves(&self) -> MoveList {
+        self.staged_moves(MoveStage::All)
+    }
+
+    ///
+    /// Generate the captures, en passant captures and promotions of the side to move
+    ///
+    pub fn capture_moves(&self) -> MoveList {
+        self.staged_moves(MoveStage::Captures)
+    }
+
+    ///
+    /// Generate the moves of the side to move which neither capture nor promote,
+    /// castling included
+    ///
+    pub fn quiet_moves(&self) -> MoveList {
+        self.staged_moves(MoveStage::Quiets)
+    }
+
+    ///
+    /// Generate the quiet moves of the side to move which put the enemy king in check
+    ///
+    pub fn quiet_checks(&self) -> MoveList {
+        self.staged_moves(MoveStage::QuietChecks)
+    }
+
+    ///
+    /// Generate the moves getting the side to move out of check,
+    /// which is none at all when they are not in check
+    ///
+    /// Every legal move of a position in check is an evasion,
+    /// as the legality masks only let pieces capture or block the checker
+    ///
+    pub fn evasion_moves(&self) -> MoveList {
+        if self.is_in_check() {
+            self.staged_moves(MoveStage::All)
+        } else {
+            MoveList::new()
+        }
+    }
+
+    fn staged_moves(&self, stage: MoveStage) -> MoveList {
+        match self.side_to_move() {
+            Team::White => self.static_get_moves::<GWhite>(stage),
+            Team::Black => self.static_get_moves::<GBlack>(stage),
+        }
+    }
+
+    // Inlined into each entry point, so the checks on the stage fold away
+    #[inline(always)]
+    fn static_get_moves<T: TTeam>(&self, stage: MoveStage) -> MoveList {
         let bb = match T::TEAM {
             Team::White => self.board.whites,
             Team::Black => self.board.blacks,
@@ -1150,26 +1812,62 @@ impl Game {
         let queens = bb & self.board.queens;
         let bishops = bb & self.board.bishops;
 
+        let masks = self.board.legality_masks::<T>();
+        let mut moves = MoveList::new();
+
+        let world = self.board.whites | self.board.blacks;
+        let base = match stage {
+            MoveStage::All => EVERYWHERE,
+            MoveStage::Captures => self.board.team_pieces::<T::Enemy>(),
+            MoveStage::Quiets | MoveStage::QuietChecks => world.negative(),
+        };
+
+        // Quiet checks land where the piece attacks the enemy king, or uncover
+        // an attack on it by stepping off the line between it and a slider
+        let quiet_checks = stage == MoveStage::QuietChecks;
+        let enemy_king = self.board.pieces::<T::Enemy, GKing>().piece_position();
+        let (pawn_checks, knight_checks, bishop_checks, rook_checks, discoverers) = if quiet_checks {
+            (
+                movement::pawn_attackers::<T::Enemy>(enemy_king),
+                movement::knight_moves(enemy_king),
+                movement::bishop_moves(enemy_king, world),
+                movement::rook_moves(enemy_king, world),
+                self.board.king_blockers::<T::Enemy>() & bb,
+            )
+        } else {
+            Default::default()
+        };
+        let targets = |checks: Bitboard, pos: Position| {
+            if !quiet_checks {
+                return base;
+            }
+            let discovery = if (discoverers & Bitboard::from(pos)).empty() {
+                Bitboard::default()
+            } else {
+                movement::line(enemy_king, pos).negative()
+            };
+            base & (checks | discovery)
+        };
+
         for pos in pawns.positions() {
-            self.get_pawn_moves::<T>(pos, move_buf);
+            self.get_pawn_moves::<T>(pos, &masks, stage, targets(pawn_checks, pos), &mut moves);
         }
         for pos in rooks.positions() {
-            self.get_rook_moves::<T>(pos, move_buf);
+            self.get_rook_moves::<T>(pos, &masks, targets(rook_checks, pos), &mut moves);
         }
         for pos in knights.positions() {
-            self.get_knight_moves::<T>(pos, move_buf);
+            self.get_knight_moves::<T>(pos, &masks, targets(knight_checks, pos), &mut moves);
         }
         for pos in kings.positions() {
-            self.get_king_moves::<T>(pos, move_buf);
+            self.get_king_moves::<T>(pos, &masks, stage, targets(Bitboard::default(), pos), &mut moves);
         }
         for pos in queens.positions() {
-            self.get_queen_moves::<T>(pos, move_buf);
+            self.get_queen_moves::<T>(pos, &masks, targets(rook_checks | bishop_checks, pos), &mut moves);
         }
         for pos in bishops.positions() {
-            self.get_bishop_moves::<T>(pos, move_buf);
+            self.get_bishop_moves::<T>(pos, &masks, targets(bishop_checks, pos), &mut moves);
         }
 
-
-        move_buf
+        moves
     }
 }
@@ -12,3 +12,11 @@ pub mod movement;
 pub mod precalc;
 pub mod magic_bitboard;
 pub mod game;
+pub mod san;
+pub mod pgn;
+pub mod eval;
+pub mod search;
+pub mod tt;
+pub mod book;
+pub mod move_list;
+pub mod packed_move;
@@ -0,0 +1,158 @@
+//!
+//! Fixed capacity lists of moves, stored inline
+//!
+//! Move generation fills these rather than a `Vec`,
+//! so searching and counting moves never touches the allocator
+//!
+
+use crate::game::GameMove;
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+
+///
+/// Room for the moves of any position, the most known being 218
+///
+pub const MOVE_LIST_CAPACITY: usize = 256;
+
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [MaybeUninit<GameMove>; MOVE_LIST_CAPACITY],
+    len: usize,
+}
+
+impl MoveList {
+    pub const fn new() -> Self {
+        Self {
+            moves: [MaybeUninit::uninit(); MOVE_LIST_CAPACITY],
+            len: 0,
+        }
+    }
+
+    ///
+    /// Add a move to the end of the list
+    ///
+    /// Panics if the list is full, which no legal position can cause
+    ///
+    pub const fn push(&mut self, mov: GameMove) {
+        self.moves[self.len] = MaybeUninit::new(mov);
+        self.len += 1;
+    }
+
+    pub const fn pop(&mut self) -> Option<GameMove> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // SAFETY: Every move below the length has been written
+        Some(unsafe { self.moves[self.len].assume_init() })
+    }
+
+    pub const fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    ///
+    /// Keep only the moves matching the predicate, in their original order
+    ///
+    pub fn retain(&mut self, mut keep: impl FnMut(&GameMove) -> bool) {
+        let mut kept = 0;
+        for i in 0..self.len {
+            let mov = self[i];
+            if keep(&mov) {
+                self.moves[kept] = MaybeUninit::new(mov);
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [GameMove];
+
+    fn deref(&self) -> &[GameMove] {
+        // SAFETY: Every move below the length has been written
+        unsafe { std::slice::from_raw_parts(self.moves.as_ptr().cast(), self.len) }
+    }
+}
+
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [GameMove] {
+        // SAFETY: Every move below the length has been written
+        unsafe { std::slice::from_raw_parts_mut(self.moves.as_mut_ptr().cast(), self.len) }
+    }
+}
+
+impl std::fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for MoveList {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for MoveList {}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a GameMove;
+    type IntoIter = std::slice::Iter<'a, GameMove>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for MoveList {
+    type Item = GameMove;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter { list: self, next: 0 }
+    }
+}
+
+impl FromIterator<GameMove> for MoveList {
+    fn from_iter<I: IntoIterator<Item = GameMove>>(iter: I) -> Self {
+        let mut list = Self::new();
+        for mov in iter {
+            list.push(mov);
+        }
+        list
+    }
+}
+
+///
+/// Iterator over the moves of a list, taken by value
+///
+#[derive(Clone, Debug)]
+pub struct IntoIter {
+    list: MoveList,
+    next: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = GameMove;
+
+    fn next(&mut self) -> Option<GameMove> {
+        let mov = self.list.get(self.next).copied();
+        self.next += 1;
+        mov
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.list.len().saturating_sub(self.next);
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
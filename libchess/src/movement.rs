@@ -121,3 +121,34 @@ pub fn pawn_attackers<T : TTeam>(pos : Position) -> Bitboard{
         Team::Black => unsafe{ *B_PAWN_ATTACKS.get_unchecked(pos.integral() as usize) }
     }
 }
+
+///
+/// The squares strictly between two positions sharing a row, column or diagonal,
+/// empty when the positions are not aligned
+///
+pub fn between(a: Position, b: Position) -> Bitboard {
+    let (a_bb, b_bb) = (Bitboard::from(a), Bitboard::from(b));
+    if !(rook_moves(a, Bitboard::default()) & b_bb).empty() {
+        rook_moves(a, b_bb) & rook_moves(b, a_bb)
+    } else if !(bishop_moves(a, Bitboard::default()) & b_bb).empty() {
+        bishop_moves(a, b_bb) & bishop_moves(b, a_bb)
+    } else {
+        Bitboard::default()
+    }
+}
+
+///
+/// The whole row, column or diagonal running through two positions,
+/// empty when the positions are not aligned
+///
+pub fn line(a: Position, b: Position) -> Bitboard {
+    let (a_bb, b_bb) = (Bitboard::from(a), Bitboard::from(b));
+    let empty = Bitboard::default();
+    if !(rook_moves(a, empty) & b_bb).empty() {
+        rook_moves(a, empty) & rook_moves(b, empty) | a_bb | b_bb
+    } else if !(bishop_moves(a, empty) & b_bb).empty() {
+        bishop_moves(a, empty) & bishop_moves(b, empty) | a_bb | b_bb
+    } else {
+        empty
+    }
+}
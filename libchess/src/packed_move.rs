@@ -0,0 +1,98 @@
+//!
+//! Moves packed into 16 bits
+//!
+//! A `GameMove` carries the moving piece along with it, which makes it
+//! bulky to keep in tables. A packed move keeps only what is needed to
+//! find the move again in a position, and is unpacked with `Game::unpack_move`
+//!
+
+use crate::game::{GameMove, Promotion};
+use positioning::Position;
+
+const SQUARE_MASK: u16 = 0x3F;
+const FROM_SHIFT: u16 = 6;
+const PROMOTION_SHIFT: u16 = 12;
+const KIND_SHIFT: u16 = 14;
+
+///
+/// What kind of move is packed, in the top two bits
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackedKind {
+    Normal = 0,
+    Promotion = 1,
+    Enpassant = 2,
+    Castle = 3,
+}
+
+///
+/// A move packed as the destination square in the low 6 bits,
+/// then the origin square, then the promotion piece and the kind of move
+///
+/// Castling is packed as the king's two-square move
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PackedMove(pub u16);
+
+impl PackedMove {
+    ///
+    /// Not a move at all, as nothing moves from a1 to a1
+    ///
+    pub const NULL: Self = Self(0);
+
+    pub const fn new(from: Position, to: Position, kind: PackedKind, promotion: Option<Promotion>) -> Self {
+        let promotion = match promotion {
+            None | Some(Promotion::Knight) => 0,
+            Some(Promotion::Bishop) => 1,
+            Some(Promotion::Rook) => 2,
+            Some(Promotion::Queen) => 3,
+        };
+        Self(
+            to.integral() as u16
+                | (from.integral() as u16) << FROM_SHIFT
+                | promotion << PROMOTION_SHIFT
+                | (kind as u16) << KIND_SHIFT,
+        )
+    }
+
+    pub const fn from_pos(self) -> Position {
+        Position::from_integral(((self.0 >> FROM_SHIFT) & SQUARE_MASK) as u8)
+    }
+
+    pub const fn to_pos(self) -> Position {
+        Position::from_integral((self.0 & SQUARE_MASK) as u8)
+    }
+
+    pub const fn kind(self) -> PackedKind {
+        match self.0 >> KIND_SHIFT {
+            0 => PackedKind::Normal,
+            1 => PackedKind::Promotion,
+            2 => PackedKind::Enpassant,
+            _ => PackedKind::Castle,
+        }
+    }
+
+    pub const fn promotion(self) -> Option<Promotion> {
+        if !matches!(self.kind(), PackedKind::Promotion) {
+            return None;
+        }
+        Some(match (self.0 >> PROMOTION_SHIFT) & 0b11 {
+            0 => Promotion::Knight,
+            1 => Promotion::Bishop,
+            2 => Promotion::Rook,
+            _ => Promotion::Queen,
+        })
+    }
+}
+
+impl From<GameMove> for PackedMove {
+    fn from(gmove: GameMove) -> Self {
+        let kind = match gmove {
+            GameMove::Promote { .. } | GameMove::CapturePromote { .. } => PackedKind::Promotion,
+            GameMove::Enpassant(_) => PackedKind::Enpassant,
+            GameMove::CastleKingside(_) | GameMove::CastleQueenside(_) => PackedKind::Castle,
+            GameMove::Regular(_) | GameMove::Capture(_) => PackedKind::Normal,
+        };
+        Self::new(gmove.from_pos(), gmove.to_pos(), kind, gmove.promotion())
+    }
+}
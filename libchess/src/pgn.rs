@@ -0,0 +1,584 @@
+//!
+//! Portable Game Notation
+//!
+//! Reading and writing whole games, including their tags,
+//! comments, NAGs and recursive variations
+//!
+
+use crate::game::{FenParseError, Game, GameMove, GameState};
+use crate::piece::Team;
+use crate::san::SanParseError;
+
+///
+/// The tags every exported game carries, in their required order
+///
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+///
+/// Lines of exported movetext are wrapped at this many characters
+///
+const LINE_WIDTH: usize = 80;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum PgnParseError {
+    #[error("Malformed tag pair '{0}'")]
+    BadTag(String),
+    #[error("Unterminated comment")]
+    UnterminatedComment,
+    #[error("Unbalanced variation")]
+    UnbalancedVariation,
+    #[error("Unexpected token '{0}'")]
+    UnexpectedToken(String),
+    #[error("Invalid FEN tag: {0}")]
+    BadFen(#[from] FenParseError),
+    #[error("Could not play '{san}': {reason}")]
+    BadMove { san: String, reason: SanParseError },
+    #[error("No game found")]
+    Empty,
+}
+
+///
+/// A single move of a game, along with its annotations
+/// and any alternatives to it
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnNode {
+    pub mov: GameMove,
+    pub nags: Vec<u8>,
+
+    ///
+    /// Commentary placed before the move,
+    /// usually only seen at the start of a variation
+    ///
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+
+    ///
+    /// Lines which could have been played instead of this move,
+    /// each starting from the position before it
+    ///
+    pub variations: Vec<Vec<Self>>,
+}
+
+impl PgnNode {
+    pub const fn new(mov: GameMove) -> Self {
+        Self {
+            mov,
+            nags: Vec::new(),
+            comment_before: None,
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+    ///
+    /// Tag pairs in the order they were read or set
+    ///
+    pub tags: Vec<(String, String)>,
+
+    ///
+    /// The position the game starts from,
+    /// taken from the FEN tag when one is present
+    ///
+    pub start: Game,
+
+    ///
+    /// The main line of the game
+    ///
+    pub moves: Vec<PgnNode>,
+}
+
+impl Default for PgnGame {
+    fn default() -> Self {
+        Self::new(Game::default())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    VariationStart,
+    VariationEnd,
+    Result(String),
+    San(String),
+}
+
+const fn suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix.as_bytes() {
+        b"!" => Some(1),
+        b"?" => Some(2),
+        b"!!" => Some(3),
+        b"??" => Some(4),
+        b"!?" => Some(5),
+        b"?!" => Some(6),
+        _ => None,
+    }
+}
+
+fn is_result(symbol: &str) -> bool {
+    matches!(symbol, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+fn read_tag(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Token, PgnParseError> {
+    let mut raw = String::from("[");
+    let mut name = String::new();
+    let mut value = String::new();
+
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+    while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphanumeric() || **c == '_') {
+        name.push(c);
+        raw.push(c);
+        chars.next();
+    }
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+
+    if name.is_empty() || chars.next() != Some('"') {
+        return Err(PgnParseError::BadTag(raw + &name));
+    }
+    loop {
+        match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+                Some(c @ ('"' | '\\')) => value.push(c),
+                _ => return Err(PgnParseError::BadTag(raw + " \"" + &value)),
+            },
+            Some(c) => value.push(c),
+            None => return Err(PgnParseError::BadTag(raw + " \"" + &value)),
+        }
+    }
+
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+    if chars.next() != Some(']') {
+        return Err(PgnParseError::BadTag(format!("{raw} \"{value}\"")));
+    }
+    Ok(Token::Tag(name, value))
+}
+
+fn tokenize(pgn: &str) -> Result<Vec<Token>, PgnParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = pgn.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        // Escaped lines are only recognised in the first column
+        if c == '%' && line_start {
+            chars.by_ref().find(|&c| c == '\n');
+            continue;
+        }
+        line_start = c == '\n';
+
+        match c {
+            _ if c.is_whitespace() => {}
+            '[' => tokens.push(read_tag(&mut chars)?),
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => comment.push(c),
+                        None => return Err(PgnParseError::UnterminatedComment),
+                    }
+                }
+                tokens.push(Token::Comment(comment.trim().to_owned()));
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|&c| c != '\n').collect();
+                line_start = true;
+                tokens.push(Token::Comment(comment.trim().to_owned()));
+            }
+            '(' => tokens.push(Token::VariationStart),
+            ')' => tokens.push(Token::VariationEnd),
+            '$' => {
+                let mut digits = String::new();
+                while let Some(&d) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                    digits.push(d);
+                    chars.next();
+                }
+                let nag = digits
+                    .parse()
+                    .map_err(|_| PgnParseError::UnexpectedToken(format!("${digits}")))?;
+                tokens.push(Token::Nag(nag));
+            }
+            _ => {
+                let mut symbol = String::from(c);
+                while let Some(&c) = chars
+                    .peek()
+                    .filter(|c| c.is_ascii_alphanumeric() || "_+#=:-/!?.".contains(**c))
+                {
+                    symbol.push(c);
+                    chars.next();
+                }
+
+                if is_result(&symbol) {
+                    tokens.push(Token::Result(symbol));
+                    continue;
+                }
+
+                // Move numbers, which may be glued onto the move itself as in `1.e4`
+                let digits = symbol.bytes().take_while(u8::is_ascii_digit).count();
+                if digits > 0 && symbol[digits..].starts_with('.') {
+                    symbol = symbol[digits..].trim_start_matches('.').to_owned();
+                    if symbol.is_empty() {
+                        continue;
+                    }
+                }
+
+                let san_len = symbol.trim_end_matches(['!', '?']).len();
+                let (san, suffix) = symbol.split_at(san_len);
+                if !san.is_empty() {
+                    tokens.push(Token::San(san.to_owned()));
+                }
+                if !suffix.is_empty() {
+                    let nag = suffix_nag(suffix)
+                        .ok_or_else(|| PgnParseError::UnexpectedToken(suffix.to_owned()))?;
+                    tokens.push(Token::Nag(nag));
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn append_comment(target: &mut Option<String>, comment: String) {
+    match target {
+        Some(existing) => {
+            existing.push(' ');
+            existing.push_str(&comment);
+        }
+        None => *target = Some(comment),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn parse_game(&mut self) -> Result<Option<PgnGame>, PgnParseError> {
+        let mut tags = Vec::new();
+        while let Some(Token::Tag(..)) = self.peek() {
+            if let Some(Token::Tag(name, value)) = self.next() {
+                tags.push((name, value));
+            }
+        }
+
+        if tags.is_empty() && self.peek().is_none() {
+            return Ok(None);
+        }
+
+        let start = match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => Game::from_fen_str(fen)?,
+            None => Game::default(),
+        };
+
+        let mut game = PgnGame {
+            tags,
+            moves: Vec::new(),
+            start: start.clone(),
+        };
+        game.moves = self.parse_line(start, false)?;
+
+        if let Some(Token::Result(result)) = self.peek() {
+            if game.tag("Result").is_none() {
+                let result = result.clone();
+                game.set_tag("Result", &result);
+            }
+            self.next();
+        }
+        Ok(Some(game))
+    }
+
+    ///
+    /// Read moves until the end of the current line,
+    /// replaying them from `pos` so each can be resolved
+    ///
+    fn parse_line(&mut self, mut pos: Game, in_variation: bool) -> Result<Vec<PgnNode>, PgnParseError> {
+        let mut nodes: Vec<PgnNode> = Vec::new();
+        let mut before: Option<Game> = None;
+        let mut pending_comment = None;
+
+        loop {
+            match self.peek() {
+                None | Some(Token::Tag(..) | Token::Result(_)) => {
+                    if in_variation {
+                        return Err(PgnParseError::UnbalancedVariation);
+                    }
+                    break;
+                }
+                Some(Token::VariationEnd) => {
+                    if !in_variation {
+                        return Err(PgnParseError::UnbalancedVariation);
+                    }
+                    self.next();
+                    break;
+                }
+                Some(Token::VariationStart) => {
+                    self.next();
+                    let (Some(last), Some(before)) = (nodes.last_mut(), &before) else {
+                        return Err(PgnParseError::UnexpectedToken("(".to_owned()));
+                    };
+                    last.variations.push(self.parse_line(before.clone(), true)?);
+                }
+                Some(Token::Comment(_)) => {
+                    let Some(Token::Comment(comment)) = self.next() else {
+                        unreachable!()
+                    };
+                    match nodes.last_mut() {
+                        Some(last) if pending_comment.is_none() => append_comment(&mut last.comment, comment),
+                        _ => append_comment(&mut pending_comment, comment),
+                    }
+                }
+                Some(&Token::Nag(nag)) => {
+                    self.next();
+                    let Some(last) = nodes.last_mut() else {
+                        return Err(PgnParseError::UnexpectedToken(format!("${nag}")));
+                    };
+                    last.nags.push(nag);
+                }
+                Some(Token::San(san)) => {
+                    let san = san.clone();
+                    self.next();
+                    let mov = pos
+                        .parse_san_move(&san)
+                        .map_err(|reason| PgnParseError::BadMove { san, reason })?;
+
+                    let after = pos.after_move(mov);
+                    before = Some(std::mem::replace(&mut pos, after));
+
+                    let mut node = PgnNode::new(mov);
+                    node.comment_before = pending_comment.take();
+                    nodes.push(node);
+                }
+            }
+        }
+
+        // A comment with no move after it still belongs to the line
+        if let (Some(comment), Some(last)) = (pending_comment, nodes.last_mut()) {
+            append_comment(&mut last.comment, comment);
+        }
+        Ok(nodes)
+    }
+}
+
+fn escape_tag_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+///
+/// Lay out movetext tokens, wrapping lines
+/// and keeping parentheses tight against their contents
+///
+fn wrap_tokens(tokens: &[String]) -> String {
+    let mut ret = String::new();
+    let mut line_len = 0;
+    let mut prev: Option<&str> = None;
+
+    for token in tokens {
+        let spaced = prev.is_some_and(|p| p != "(") && token != ")";
+        let needed = token.len() + usize::from(spaced);
+
+        if spaced && line_len + needed > LINE_WIDTH {
+            ret.push('\n');
+            line_len = 0;
+        } else if spaced {
+            ret.push(' ');
+            line_len += 1;
+        }
+        ret += token;
+        line_len += token.len();
+        prev = Some(token);
+    }
+    ret
+}
+
+fn write_line(tokens: &mut Vec<String>, start: &Game, nodes: &[PgnNode]) {
+    let mut pos = start.clone();
+    let mut need_number = true;
+
+    for node in nodes {
+        if let Some(comment) = &node.comment_before {
+            tokens.push(format!("{{{comment}}}"));
+            need_number = true;
+        }
+
+        if pos.side_to_move() == Team::White {
+            tokens.push(format!("{}.", pos.fullmove_num));
+        } else if need_number {
+            tokens.push(format!("{}...", pos.fullmove_num));
+        }
+        tokens.push(pos.move_to_san(node.mov));
+
+        tokens.extend(node.nags.iter().map(|nag| format!("${nag}")));
+        if let Some(comment) = &node.comment {
+            tokens.push(format!("{{{comment}}}"));
+        }
+        need_number = node.comment.is_some() || !node.variations.is_empty();
+
+        for variation in &node.variations {
+            tokens.push("(".to_owned());
+            write_line(tokens, &pos, variation);
+            tokens.push(")".to_owned());
+        }
+        pos = pos.after_move(node.mov);
+    }
+}
+
+impl PgnGame {
+    ///
+    /// Start an empty game from the given position
+    ///
+    pub const fn new(start: Game) -> Self {
+        Self {
+            tags: Vec::new(),
+            start,
+            moves: Vec::new(),
+        }
+    }
+
+    ///
+    /// Read the first game of a PGN document
+    ///
+    pub fn parse(pgn: &str) -> Result<Self, PgnParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(pgn)?,
+            index: 0,
+        };
+        parser.parse_game()?.ok_or(PgnParseError::Empty)
+    }
+
+    ///
+    /// Read every game of a PGN document
+    ///
+    pub fn parse_all(pgn: &str) -> Result<Vec<Self>, PgnParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(pgn)?,
+            index: 0,
+        };
+
+        let mut games = Vec::new();
+        while let Some(game) = parser.parse_game()? {
+            games.push(game);
+        }
+        Ok(games)
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    ///
+    /// Set a tag, replacing any existing value
+    ///
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => value.clone_into(existing),
+            None => self.tags.push((name.to_owned(), value.to_owned())),
+        }
+    }
+
+    ///
+    /// Append a move to the main line,
+    /// it must be legal in the final position
+    ///
+    pub fn push_move(&mut self, mov: GameMove) {
+        self.moves.push(PgnNode::new(mov));
+    }
+
+    pub fn mainline(&self) -> impl Iterator<Item = GameMove> + '_ {
+        self.moves.iter().map(|node| node.mov)
+    }
+
+    ///
+    /// Replay the main line, returning the position it ends in
+    ///
+    pub fn final_position(&self) -> Game {
+        self.mainline()
+            .fold(self.start.clone(), |pos, mov| pos.after_move(mov))
+    }
+
+    ///
+    /// The result of the game, taken from the final position when it
+    /// has been decided on the board, and otherwise from the Result tag
+    ///
+    pub fn result(&self) -> &str {
+        match self.final_position().state {
+            GameState::WhiteVictory => "1-0",
+            GameState::BlackVictory => "0-1",
+            GameState::Draw { .. } => "1/2-1/2",
+            GameState::WhiteToMove | GameState::BlackToMove => {
+                self.tag("Result").filter(|r| is_result(r)).unwrap_or("*")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let result = self.result();
+
+        let mut tags: Vec<(&str, String)> = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|&(name, default)| (name, self.tag(name).unwrap_or(default).to_owned()))
+            .collect();
+        tags[6].1 = result.to_owned();
+
+        let start_fen = self.start.to_fen_str();
+        if start_fen != Game::default().to_fen_str() {
+            tags.push(("SetUp", "1".to_owned()));
+            tags.push(("FEN", start_fen));
+        }
+
+        for (name, value) in &self.tags {
+            let known = SEVEN_TAG_ROSTER.iter().any(|(n, _)| n == name) || name == "SetUp" || name == "FEN";
+            if !known {
+                tags.push((name, value.clone()));
+            }
+        }
+
+        for (name, value) in tags {
+            writeln!(f, "[{name} \"{}\"]", escape_tag_value(&value))?;
+        }
+        writeln!(f)?;
+
+        let mut tokens = Vec::new();
+        write_line(&mut tokens, &self.start, &self.moves);
+        tokens.push(result.to_owned());
+
+        let mut movetext = wrap_tokens(&tokens);
+        movetext.push('\n');
+        f.write_str(&movetext)
+    }
+}
@@ -0,0 +1,226 @@
+//!
+//! Standard Algebraic Notation
+//!
+//! Reading and writing moves the way they appear in
+//! printed games and PGN files, i.e `Nbd2`, `exd5` or `e8=Q+`
+//!
+
+use crate::game::{Game, GameMove, GameState, MoveGenerationError, Promotion};
+use crate::piece::PieceKind;
+use positioning::Position;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum SanParseError {
+    #[error("Malformed SAN move '{0}'")]
+    Malformed(String),
+    #[error("Illegal move '{0}'")]
+    Illegal(String),
+    #[error("Ambiguous move '{0}'")]
+    Ambiguous(String),
+    #[error("Game Finished")]
+    GameFinished(GameState),
+}
+
+const fn piece_letter(kind: PieceKind) -> Option<char> {
+    match kind {
+        PieceKind::King => Some('K'),
+        PieceKind::Queen => Some('Q'),
+        PieceKind::Rook => Some('R'),
+        PieceKind::Bishop => Some('B'),
+        PieceKind::Knight => Some('N'),
+        PieceKind::Pawn => None,
+    }
+}
+
+const fn promotion_from_letter(letter: char) -> Option<Promotion> {
+    match letter.to_ascii_uppercase() {
+        'Q' => Some(Promotion::Queen),
+        'R' => Some(Promotion::Rook),
+        'B' => Some(Promotion::Bishop),
+        'N' => Some(Promotion::Knight),
+        _ => None,
+    }
+}
+
+const fn file_char(pos: Position) -> char {
+    (b'a' + pos.col()) as char
+}
+
+const fn rank_char(pos: Position) -> char {
+    (b'1' + pos.row()) as char
+}
+
+impl Game {
+    ///
+    /// Write a legal move of the current position in SAN,
+    /// including any disambiguation and check or mate suffix
+    ///
+    pub fn move_to_san(&self, mov: GameMove) -> String {
+        let mut ret = match mov {
+            GameMove::CastleKingside(_) => "O-O".to_owned(),
+            GameMove::CastleQueenside(_) => "O-O-O".to_owned(),
+            _ => self.san_body(mov),
+        };
+
+        let after = self.after_move(mov);
+        match after.state {
+            GameState::WhiteVictory | GameState::BlackVictory => ret.push('#'),
+            _ if after.is_in_check() => ret.push('+'),
+            _ => {}
+        }
+        ret
+    }
+
+    fn san_body(&self, mov: GameMove) -> String {
+        let from = mov.from_pos();
+        let to = mov.to_pos();
+        let mut ret = String::new();
+
+        match piece_letter(mov.piece_kind()) {
+            Some(letter) => {
+                ret.push(letter);
+
+                // Other pieces of the same kind which could also reach the square
+                let rivals: Vec<_> = self
+                    .legal_moves()
+                    .iter()
+                    .filter(|m| {
+                        m.piece_kind() == mov.piece_kind()
+                            && m.to_pos() == to
+                            && m.from_pos() != from
+                    })
+                    .map(GameMove::from_pos)
+                    .collect();
+
+                if !rivals.is_empty() {
+                    if rivals.iter().all(|r| r.col() != from.col()) {
+                        ret.push(file_char(from));
+                    } else if rivals.iter().all(|r| r.row() != from.row()) {
+                        ret.push(rank_char(from));
+                    } else {
+                        ret.push(file_char(from));
+                        ret.push(rank_char(from));
+                    }
+                }
+            }
+            None => {
+                if mov.is_capture() {
+                    ret.push(file_char(from));
+                }
+            }
+        }
+
+        if mov.is_capture() {
+            ret.push('x');
+        }
+        ret += &to.as_alphanum().to_lowercase();
+
+        if let Some(promotion) = mov.promotion() {
+            ret.push('=');
+            ret.extend(piece_letter(promotion.piece_kind()));
+        }
+        ret
+    }
+
+    ///
+    /// Resolve a SAN move against the legal moves of the current position
+    ///
+    /// Common variations are tolerated, such as `0-0` for castling,
+    /// a missing capture marker, and promotions without the `=`
+    ///
+    pub fn parse_san_move(&self, san: &str) -> Result<GameMove, SanParseError> {
+        let malformed = || SanParseError::Malformed(san.to_owned());
+
+        let moves = self
+            .moves()
+            .map_err(|MoveGenerationError::GameFinished(state)| SanParseError::GameFinished(state))?;
+
+        // Check, mate and annotation suffixes carry no information about the move
+        let body = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        if !body.is_ascii() {
+            return Err(malformed());
+        }
+
+        let find_unique = |mut candidates: Vec<GameMove>| match candidates.len() {
+            0 => Err(SanParseError::Illegal(san.to_owned())),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(SanParseError::Ambiguous(san.to_owned())),
+        };
+
+        let castle = match body {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(kingside) = castle {
+            return find_unique(
+                moves
+                    .iter()
+                    .filter(|m| match m {
+                        GameMove::CastleKingside(_) => kingside,
+                        GameMove::CastleQueenside(_) => !kingside,
+                        _ => false,
+                    })
+                    .copied()
+                    .collect(),
+            );
+        }
+
+        let mut chars: Vec<char> = body
+            .chars()
+            .filter(|c| !matches!(c, 'x' | ':' | '-'))
+            .collect();
+
+        // A promotion is the only thing which may follow the destination rank
+        let mut promotion = None;
+        if let Some(&letter) = chars.last().filter(|c| c.is_ascii_alphabetic()) {
+            promotion = Some(promotion_from_letter(letter).ok_or_else(malformed)?);
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+
+        let kind = match chars.first() {
+            Some('K') => PieceKind::King,
+            Some('Q') => PieceKind::Queen,
+            Some('R') => PieceKind::Rook,
+            Some('B') => PieceKind::Bishop,
+            Some('N') => PieceKind::Knight,
+            _ => PieceKind::Pawn,
+        };
+        if kind != PieceKind::Pawn {
+            chars.remove(0);
+        }
+
+        if chars.len() < 2 {
+            return Err(malformed());
+        }
+        let dest: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to = Position::from_alphanum(&dest).ok_or_else(malformed)?;
+
+        let mut from_col = None;
+        let mut from_row = None;
+        for c in chars {
+            match c {
+                'a'..='h' if from_col.is_none() => from_col = Some(c as u8 - b'a'),
+                '1'..='8' if from_row.is_none() => from_row = Some(c as u8 - b'1'),
+                _ => return Err(malformed()),
+            }
+        }
+
+        find_unique(
+            moves
+                .iter()
+                .filter(|m| {
+                    m.piece_kind() == kind
+                        && m.to_pos() == to
+                        && m.promotion() == promotion
+                        && from_col.is_none_or(|c| m.from_pos().col() == c)
+                        && from_row.is_none_or(|r| m.from_pos().row() == r)
+                })
+                .copied()
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,418 @@
+//!
+//! Game tree search
+//!
+//! Negamax with alpha-beta pruning, a transposition table and a capture-only
+//! quiescence search, driven by iterative deepening until a depth, node or
+//! time limit is hit or the caller raises the stop flag
+//!
+
+use crate::board::ChessBoard;
+use crate::eval::{piece_value, Evaluator};
+use crate::game::{Game, GameMove, GameState};
+use crate::move_list::MoveList;
+use crate::packed_move::PackedMove;
+use crate::piece::PieceKind;
+use crate::tt::{Bound, SearchEntry, TranspositionTable};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+///
+/// The score of delivering mate on the spot,
+/// mates further away score one less per ply
+///
+pub const MATE_SCORE: i32 = 32_000;
+
+///
+/// The deepest the search will ever look, in plies
+///
+pub const MAX_PLY: usize = 128;
+
+const INFINITY: i32 = MATE_SCORE + 1;
+
+///
+/// How many nodes are searched between checks of the clock
+///
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+
+    ///
+    /// Mate in the given number of moves,
+    /// negative when the side to move is the one being mated
+    ///
+    Mate(i32),
+}
+
+impl Score {
+    pub const fn from_raw(raw: i32) -> Self {
+        let mate_bound = MATE_SCORE - MAX_PLY as i32;
+        if raw > mate_bound {
+            Self::Mate((MATE_SCORE - raw + 1) / 2)
+        } else if raw < -mate_bound {
+            Self::Mate(-(MATE_SCORE + raw) / 2)
+        } else {
+            Self::Centipawns(raw)
+        }
+    }
+}
+
+///
+/// Formats the score the way UCI `info` lines expect it
+///
+impl std::fmt::Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Centipawns(cp) => write!(f, "cp {cp}"),
+            Self::Mate(moves) => write!(f, "mate {moves}"),
+        }
+    }
+}
+
+///
+/// Conditions for ending a search, with no limits at all
+/// the search runs until the stop flag is raised
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchReport {
+    ///
+    /// The deepest iteration which was completed
+    ///
+    pub depth: u32,
+    pub score: Score,
+    pub nodes: u64,
+    pub elapsed: Duration,
+
+    ///
+    /// The principal variation, starting with the best move
+    ///
+    pub pv: Vec<GameMove>,
+}
+
+impl SearchReport {
+    pub fn best_move(&self) -> Option<GameMove> {
+        self.pv.first().copied()
+    }
+}
+
+const fn is_mate(score: i32) -> bool {
+    score.abs() > MATE_SCORE - MAX_PLY as i32
+}
+
+///
+/// Mate scores are stored relative to the position rather than the root,
+/// so they stay correct when the position is reached at another ply
+///
+const fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if !is_mate(score) {
+        score
+    } else if score > 0 {
+        score + ply as i32
+    } else {
+        score - ply as i32
+    }
+}
+
+const fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if !is_mate(score) {
+        score
+    } else if score > 0 {
+        score - ply as i32
+    } else {
+        score + ply as i32
+    }
+}
+
+///
+/// Most valuable victim, least valuable attacker
+///
+fn move_order_key(board: &ChessBoard, mov: &GameMove) -> i32 {
+    let victim = if mov.is_capture() {
+        board
+            .get_piece_info(mov.to_pos())
+            .map_or(piece_value(PieceKind::Pawn), |p| piece_value(p.kind))
+    } else {
+        0
+    };
+    let promotion = mov.promotion().map_or(0, |p| piece_value(p.piece_kind()));
+
+    -(victim * 10 + promotion - piece_value(mov.piece_kind()) / 100)
+}
+
+struct Searcher<'s, E: ?Sized> {
+    evaluator: &'s E,
+    tt: &'s TranspositionTable<SearchEntry>,
+    limits: SearchLimits,
+    stop: &'s AtomicBool,
+    started: Instant,
+    nodes: u64,
+    aborted: bool,
+
+    ///
+    /// Best move of the previous iteration, searched first at the root
+    ///
+    root_best: Option<GameMove>,
+}
+
+impl<E: Evaluator + ?Sized> Searcher<'_, E> {
+    fn should_abort(&mut self) -> bool {
+        if !self.aborted {
+            self.aborted = self.stop.load(Ordering::Relaxed)
+                || self.limits.nodes.is_some_and(|n| self.nodes >= n)
+                || (self.nodes.is_multiple_of(TIME_CHECK_INTERVAL)
+                    && self.limits.time.is_some_and(|t| self.started.elapsed() >= t));
+        }
+        self.aborted
+    }
+
+    ///
+    /// The score of the game if it has already been adjudicated,
+    /// as `Game::make_move` does for every position it reaches
+    ///
+    const fn outcome(game: &Game, ply: usize) -> Option<i32> {
+        match game.state {
+            GameState::WhiteToMove | GameState::BlackToMove => None,
+            GameState::Draw { .. } => Some(0),
+
+            // The side to move has been checkmated
+            GameState::WhiteVictory | GameState::BlackVictory => Some(ply as i32 - MATE_SCORE),
+        }
+    }
+
+    ///
+    /// Generate the moves of a position, or the score of the game
+    /// if it has already ended
+    ///
+    fn moves_or_outcome(game: &Game, ply: usize) -> Result<MoveList, i32> {
+        if let Some(score) = Self::outcome(game, ply) {
+            return Err(score);
+        }
+        let moves = game.legal_moves();
+        if !moves.is_empty() {
+            Ok(moves)
+        } else if game.is_in_check() {
+            Err(ply as i32 - MATE_SCORE)
+        } else {
+            Err(0)
+        }
+    }
+
+    fn negamax(
+        &mut self,
+        game: &mut Game,
+        depth: u32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<GameMove>,
+    ) -> i32 {
+        pv.clear();
+        if ply > 0 && game.repetition_count() >= 2 {
+            return 0;
+        }
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiesce(game, ply, alpha, beta);
+        }
+
+        if self.should_abort() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        let mut ordered = match Self::moves_or_outcome(game, ply) {
+            Ok(moves) => moves,
+            Err(score) => return score,
+        };
+
+        let hash = game.hash();
+        let entry = self.tt.probe(&hash);
+        if let Some(entry) = entry.filter(|e| ply > 0 && u32::from(e.depth) >= depth) {
+            let score = score_from_tt(entry.score, ply);
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if cutoff {
+                return score;
+            }
+        }
+
+        ordered.sort_by_key(|m| move_order_key(&game.board, m));
+        let root_best = if ply == 0 { self.root_best } else { None };
+        let hash_move = root_best.or_else(|| {
+            entry
+                .and_then(|e| e.best_move)
+                .filter(|&m| game.is_legal(m))
+                .and_then(|m| game.unpack_move(m))
+        });
+        if let Some(i) = ordered.iter().position(|&m| Some(m) == hash_move) {
+            ordered[..=i].rotate_right(1);
+        }
+
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+        for mov in ordered {
+            let undo = game.make_move(mov);
+            let score = -self.negamax(game, depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+            game.unmake_move(mov, undo);
+            if self.aborted {
+                return 0;
+            }
+
+            if score > best {
+                best = score;
+                best_move = Some(mov);
+                if score > alpha {
+                    alpha = score;
+                    pv.clear();
+                    pv.push(mov);
+                    pv.extend_from_slice(&child_pv);
+                }
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        let bound = if best <= original_alpha {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.tt.store(
+            &hash,
+            SearchEntry {
+                depth: depth.min(u32::from(u8::MAX)) as u8,
+                bound,
+                score: score_to_tt(best, ply),
+                best_move: best_move.map(PackedMove::from),
+            },
+        );
+        best
+    }
+
+    ///
+    /// Resolve captures and promotions so the static evaluation
+    /// is never taken in the middle of an exchange
+    ///
+    fn quiesce(&mut self, game: &mut Game, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        if self.should_abort() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        // Only ever reached through `Game::make_move`, so mates and draws are already known
+        if let Some(score) = Self::outcome(game, ply) {
+            return score;
+        }
+
+        let stand_pat = self.evaluator.evaluate(&game.board, game.side_to_move());
+        if stand_pat >= beta || ply >= MAX_PLY {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut tactical = game.capture_moves();
+        tactical.sort_by_key(|m| move_order_key(&game.board, m));
+
+        let mut best = stand_pat;
+        for mov in tactical {
+            let undo = game.make_move(mov);
+            let score = -self.quiesce(game, ply + 1, -beta, -alpha);
+            game.unmake_move(mov, undo);
+            if self.aborted {
+                return 0;
+            }
+
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
+///
+/// Search for the best move in a position by iterative deepening
+///
+/// `on_iteration` is called with the result of every completed depth,
+/// and the final report is that of the deepest completed iteration.
+/// The principal variation is empty only when the game has already ended
+///
+pub fn search<E: Evaluator + ?Sized>(
+    game: &Game,
+    evaluator: &E,
+    tt: &TranspositionTable<SearchEntry>,
+    limits: SearchLimits,
+    stop: &AtomicBool,
+    mut on_iteration: impl FnMut(&SearchReport),
+) -> SearchReport {
+    tt.new_search();
+    let mut searcher = Searcher {
+        evaluator,
+        tt,
+        limits,
+        stop,
+        started: Instant::now(),
+        nodes: 0,
+        aborted: false,
+        root_best: None,
+    };
+
+    let first_move = game.moves().ok().and_then(|m| m.first().copied());
+    let mut report = SearchReport {
+        depth: 0,
+        score: Score::Centipawns(evaluator.evaluate(&game.board, game.side_to_move())),
+        nodes: 0,
+        elapsed: Duration::ZERO,
+        pv: first_move.into_iter().collect(),
+    };
+    if first_move.is_none() {
+        return report;
+    }
+
+    // The whole tree is walked by making and unmaking moves on this one copy
+    let mut game = game.clone();
+    let max_depth = limits.depth.unwrap_or(u32::MAX).clamp(1, MAX_PLY as u32);
+    let mut pv = Vec::new();
+    for depth in 1..=max_depth {
+        let score = searcher.negamax(&mut game, depth, 0, -INFINITY, INFINITY, &mut pv);
+        if searcher.aborted {
+            break;
+        }
+
+        searcher.root_best = pv.first().copied();
+        report = SearchReport {
+            depth,
+            score: Score::from_raw(score),
+            nodes: searcher.nodes,
+            elapsed: searcher.started.elapsed(),
+            pv: pv.clone(),
+        };
+        on_iteration(&report);
+
+        // Nothing deeper can improve on a forced mate
+        if matches!(report.score, Score::Mate(moves) if moves.unsigned_abs() * 2 <= depth) {
+            break;
+        }
+    }
+
+    report.nodes = searcher.nodes;
+    report.elapsed = searcher.started.elapsed();
+    report
+}
@@ -0,0 +1,198 @@
+//!
+//! Transposition tables
+//!
+//! A fixed-size hash table of positions keyed on their zobrist hash,
+//! split into small buckets which are locked independently so the
+//! table can be shared between threads
+//!
+
+use crate::packed_move::PackedMove;
+use crate::zobrist::ZobristHash;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+
+///
+/// Number of entries competing for the same index
+///
+const BUCKET_SIZE: usize = 4;
+
+///
+/// How many buckets are inspected to estimate `hashfull`
+///
+const HASHFULL_SAMPLE: usize = 250;
+
+///
+/// Each search the entry has outlived counts
+/// against it as much as this many plies of depth
+///
+const AGE_WEIGHT: i32 = 8;
+
+///
+/// Anything which can be stored in a `TranspositionTable`
+///
+pub trait TableEntry: Copy + Default {
+    ///
+    /// How much work went into the entry,
+    /// deeper entries are kept in preference to shallower ones
+    ///
+    fn depth(&self) -> u8;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Bound {
+    ///
+    /// The score is exact
+    ///
+    #[default]
+    Exact,
+
+    ///
+    /// The search failed high, the true score is at least this
+    ///
+    Lower,
+
+    ///
+    /// The search failed low, the true score is at most this
+    ///
+    Upper,
+}
+
+///
+/// What the search remembers about a position
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchEntry {
+    pub depth: u8,
+    pub bound: Bound,
+    pub score: i32,
+
+    ///
+    /// Packed to keep entries small, and to be checked
+    /// with `Game::is_legal` before being trusted
+    ///
+    pub best_move: Option<PackedMove>,
+}
+
+impl TableEntry for SearchEntry {
+    fn depth(&self) -> u8 {
+        self.depth
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Slot<E> {
+    key: u64,
+    age: u8,
+    used: bool,
+    entry: E,
+}
+
+pub struct TranspositionTable<E> {
+    buckets: Vec<Mutex<[Slot<E>; BUCKET_SIZE]>>,
+    age: AtomicU8,
+}
+
+impl<E: TableEntry> TranspositionTable<E> {
+    ///
+    /// Create a table taking up roughly the given number of megabytes
+    ///
+    pub fn new(megabytes: usize) -> Self {
+        let bucket_bytes = std::mem::size_of::<Mutex<[Slot<E>; BUCKET_SIZE]>>();
+        let count = (megabytes * 1024 * 1024 / bucket_bytes).max(1);
+
+        Self {
+            buckets: (0..count).map(|_| Mutex::default()).collect(),
+            age: AtomicU8::new(0),
+        }
+    }
+
+    ///
+    /// Number of entries the table can hold
+    ///
+    pub const fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE
+    }
+
+    pub fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            *bucket.get_mut().unwrap_or_else(std::sync::PoisonError::into_inner) = Default::default();
+        }
+        *self.age.get_mut() = 0;
+    }
+
+    ///
+    /// Mark the start of a new search, so entries left
+    /// over from earlier ones are replaced first
+    ///
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn bucket(&self, key: u64) -> std::sync::MutexGuard<'_, [Slot<E>; BUCKET_SIZE]> {
+        // Map the hash onto the table without the bias of a modulo
+        let index = ((u128::from(key) * self.buckets.len() as u128) >> 64) as usize;
+        self.buckets[index]
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub fn probe(&self, hash: &ZobristHash) -> Option<E> {
+        let key = hash.value();
+        let age = self.age.load(Ordering::Relaxed);
+        let mut bucket = self.bucket(key);
+
+        let slot = bucket.iter_mut().find(|s| s.used && s.key == key)?;
+        slot.age = age;
+        let entry = slot.entry;
+        drop(bucket);
+        Some(entry)
+    }
+
+    ///
+    /// Store an entry, replacing any previous entry for the same position,
+    /// otherwise a free slot, otherwise the stalest and shallowest entry of the bucket
+    ///
+    pub fn store(&self, hash: &ZobristHash, entry: E) {
+        let key = hash.value();
+        let age = self.age.load(Ordering::Relaxed);
+        let mut bucket = self.bucket(key);
+
+        let priority = |slot: &Slot<E>| {
+            i32::from(slot.entry.depth()) - AGE_WEIGHT * i32::from(age.wrapping_sub(slot.age))
+        };
+        let victim = bucket
+            .iter()
+            .position(|s| s.used && s.key == key)
+            .or_else(|| bucket.iter().position(|s| !s.used))
+            .unwrap_or_else(|| {
+                (0..BUCKET_SIZE)
+                    .min_by_key(|&i| priority(&bucket[i]))
+                    .unwrap_or_default()
+            });
+
+        bucket[victim] = Slot {
+            key,
+            age,
+            used: true,
+            entry,
+        };
+    }
+
+    ///
+    /// Permill of the table in use by the current search, estimated from a sample
+    ///
+    pub fn hashfull(&self) -> u32 {
+        let age = self.age.load(Ordering::Relaxed);
+        let sampled = self.buckets.len().min(HASHFULL_SAMPLE);
+
+        let used = self.buckets[..sampled]
+            .iter()
+            .map(|b| {
+                let bucket = b.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+                bucket.iter().filter(|s| s.used && s.age == age).count()
+            })
+            .sum::<usize>();
+
+        (used * 1000 / (sampled * BUCKET_SIZE)) as u32
+    }
+}
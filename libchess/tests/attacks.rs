@@ -0,0 +1,73 @@
+use libchess::board::{ChessBoard, Pin};
+use libchess::game::Game;
+use libchess::piece::{GBlack, GWhite};
+use positioning::{Bitboard, Position};
+
+fn from_fen(fen: &str) -> ChessBoard {
+    Game::from_fen_str(fen).unwrap().board
+}
+
+fn sq(name: &str) -> Position {
+    Position::from_alphanum(name).unwrap()
+}
+
+fn squares(names: &[&str]) -> Bitboard {
+    names.iter().fold(Bitboard::default(), |bb, name| bb | Bitboard::from(sq(name)))
+}
+
+#[test]
+fn attacked_squares() {
+    let board = Game::default().board;
+    let attacked = board.attacked_squares::<GWhite>();
+
+    assert_eq!(attacked & Bitboard::ROW_3, Bitboard::ROW_3);
+    assert!((attacked & squares(&["a1", "h1", "e4"])).empty());
+
+    // Every other piece on the first two rows is defended
+    assert_eq!(attacked.count(), 22);
+    assert_eq!(board.attacked_squares::<GBlack>().count(), 22);
+}
+
+#[test]
+fn attackers_and_checkers() {
+    let board = from_fen("4k3/8/8/8/8/5n2/8/r3K3 w - - 0 1");
+    assert_eq!(board.checkers::<GWhite>(), squares(&["a1", "f3"]));
+    assert!(board.checkers::<GBlack>().empty());
+    assert_eq!(board.pos_attackers::<GBlack>(sq("d2")), squares(&["f3"]));
+    assert_eq!(board.pos_attackers::<GWhite>(sq("d2")), squares(&["e1"]));
+}
+
+#[test]
+fn pins_with_pinners() {
+    let board = from_fen("k3r3/8/8/b7/8/8/3NB3/4K3 w - - 0 1");
+    let pins: Vec<_> = board.pins::<GWhite>().collect();
+    assert_eq!(
+        pins,
+        [
+            Pin { pinned: sq("d2"), pinner: sq("a5") },
+            Pin { pinned: sq("e2"), pinner: sq("e8") },
+        ]
+    );
+    assert_eq!(board.pinned::<GWhite>(), squares(&["d2", "e2"]));
+    assert!(board.pinned::<GBlack>().empty());
+
+    // Two pieces in the way make neither pinned
+    let board = from_fen("k3r3/8/8/8/8/4N3/4B3/4K3 w - - 0 1");
+    assert!(board.pinned::<GWhite>().empty());
+}
+
+#[test]
+fn xray_attackers() {
+    let board = from_fen("r3k3/1p6/8/3B4/4Q3/8/R7/R3K3 w - - 0 1");
+
+    // The doubled rooks on the a file, and the bishop behind the b7 pawn
+    assert_eq!(board.pos_attackers::<GWhite>(sq("a8")), squares(&["a2"]));
+    assert_eq!(board.xray_attackers::<GWhite>(sq("a8")), squares(&["a1", "d5"]));
+
+    // The queen behind the bishop
+    assert_eq!(board.pos_attackers::<GWhite>(sq("b7")), squares(&["d5"]));
+    assert_eq!(board.xray_attackers::<GWhite>(sq("b7")), squares(&["e4"]));
+
+    // Through pieces of the other team too
+    assert_eq!(board.xray_attackers::<GBlack>(sq("a1")), squares(&["a8"]));
+}
@@ -0,0 +1,207 @@
+use libchess::book::{decode_move, encode_move, Book, BookBuilder, BookEntry, BookError};
+use libchess::game::{Game, GameMove};
+use libchess::pgn::PgnGame;
+use libchess::piece::Team;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn entry(game: &Game, uci: &str, weight: u16) -> BookEntry{
+    BookEntry {
+        key: game.hash().value(),
+        mov: encode_move(game.parse_uci_move(uci).unwrap()),
+        weight,
+        learn: 0,
+    }
+}
+
+fn to_bytes(entries: &[BookEntry]) -> Vec<u8>{
+    entries.iter().flat_map(BookEntry::to_bytes).collect()
+}
+
+#[test]
+fn encodes_moves(){
+    // e2e4 is square 12 to square 28
+    let game = Game::default();
+    assert_eq!(encode_move(game.parse_uci_move("e2e4").unwrap()), 28 | 12 << 6);
+
+    // Castling is the king taking its own rook
+    assert_eq!(encode_move(GameMove::CastleKingside(Team::White)), 7 | 4 << 6);
+    assert_eq!(encode_move(GameMove::CastleQueenside(Team::Black)), 56 | 60 << 6);
+
+    let game = Game::from_fen_str("8/1P6/8/8/8/8/8/K1k5 w - - 0 1").unwrap();
+    assert_eq!(encode_move(game.parse_uci_move("b7b8n").unwrap()), 57 | 49 << 6 | 1 << 12);
+    assert_eq!(encode_move(game.parse_uci_move("b7b8q").unwrap()), 57 | 49 << 6 | 4 << 12);
+}
+
+#[test]
+fn decodes_every_legal_move(){
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1",
+    ]{
+        let game = Game::from_fen_str(fen).unwrap();
+        let mut mb = Vec::new();
+        for &mov in game.get_legal_moves(&mut mb){
+            assert_eq!(decode_move(&game, encode_move(mov)), Some(mov), "{fen}");
+        }
+    }
+    // Illegal moves are rejected
+    assert_eq!(decode_move(&Game::default(), 36 | 12 << 6), None);
+}
+
+#[test]
+fn looks_up_positions(){
+    let start = Game::default();
+    let after = start.after_move(start.parse_uci_move("e2e4").unwrap());
+    let mut entries = vec![
+        entry(&after, "c7c5", 10),
+        entry(&start, "e2e4", 3),
+        entry(&after, "e7e5", 5),
+        entry(&start, "d2d4", 1),
+    ];
+    // An entry whose move does not fit the position is skipped
+    entries.push(BookEntry { mov: 36 | 12 << 6, ..entries[1] });
+
+    let book = Book::from_bytes(&to_bytes(&entries)).unwrap();
+    assert_eq!(book.len(), 5);
+    assert_eq!(book.entries(&start).len(), 3);
+
+    let uci = |moves: Vec<(GameMove, u16)>| moves.into_iter().map(|(m, w)| (m.to_uci(), w)).collect::<Vec<_>>();
+    assert_eq!(uci(book.moves(&start)), [("e2e4".to_owned(), 3), ("d2d4".to_owned(), 1)]);
+    assert_eq!(uci(book.moves(&after)), [("c7c5".to_owned(), 10), ("e7e5".to_owned(), 5)]);
+
+    let elsewhere = after.after_move(after.parse_uci_move("e7e5").unwrap());
+    assert!(book.moves(&elsewhere).is_empty());
+    assert_eq!(book.pick_weighted(&elsewhere, &mut StdRng::seed_from_u64(0)), None);
+}
+
+///
+/// A book entry as it is laid out on disk, written out by hand
+///
+fn raw_entry(key: u64, mov: u16, weight: u16) -> Vec<u8>{
+    let mut bytes = key.to_be_bytes().to_vec();
+    bytes.extend(mov.to_be_bytes());
+    bytes.extend(weight.to_be_bytes());
+    bytes.extend([0; 4]);
+    bytes
+}
+
+#[test]
+fn reads_polyglot_keyed_entries(){
+    // Keys as Polyglot computes them, rather than taken from `Game::hash`
+    let start = 0x463b96181691fc9c;
+    let castling = 0xfda239cc692a6053;
+    let promotion = 0x5b22a5dfea520fb1;
+
+    let bytes: Vec<u8> = [
+        raw_entry(start, 28 | 12 << 6, 7),
+        raw_entry(start, 27 | 11 << 6, 2),
+        // e1h1 and e1a1, the king taking its own rooks
+        raw_entry(castling, 7 | 4 << 6, 4),
+        raw_entry(castling, 4 << 6, 1),
+        // b7b8 promoting to a queen, then to a knight
+        raw_entry(promotion, 57 | 49 << 6 | 4 << 12, 9),
+        raw_entry(promotion, 57 | 49 << 6 | 1 << 12, 1),
+    ].concat();
+    let book = Book::from_bytes(&bytes).unwrap();
+
+    let uci = |fen: &str| book_moves(&book, &Game::from_fen_str(fen).unwrap());
+    assert_eq!(
+        uci("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        [("e2e4".to_owned(), 7), ("d2d4".to_owned(), 2)]
+    );
+    assert_eq!(
+        uci("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1"),
+        [("b7b8q".to_owned(), 9), ("b7b8n".to_owned(), 1)]
+    );
+
+    let game = Game::from_fen_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    let moves: Vec<_> = book.moves(&game).into_iter().map(|(m, _)| m).collect();
+    assert_eq!(moves, [GameMove::CastleKingside(Team::White), GameMove::CastleQueenside(Team::White)]);
+}
+
+#[test]
+fn picks_moves(){
+    let start = Game::default();
+    let book = Book::from_entries(vec![entry(&start, "e2e4", 3), entry(&start, "d2d4", 1), entry(&start, "c2c4", 0)]);
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let mut counts = [0; 3];
+    for _ in 0..4000{
+        match book.pick_weighted(&start, &mut rng).unwrap().to_uci().as_str(){
+            "e2e4" => counts[0] += 1,
+            "d2d4" => counts[1] += 1,
+            _ => counts[2] += 1,
+        }
+    }
+    assert!((2800..3200).contains(&counts[0]), "{counts:?}");
+    assert_eq!(counts[2], 0, "Moves without weight are never picked by weight");
+
+    let uniform = (0..300).filter(|_| book.pick_uniform(&start, &mut rng).unwrap().to_uci() == "c2c4").count();
+    assert!(uniform > 50, "{uniform}");
+}
+
+#[test]
+fn rejects_truncated_books(){
+    assert!(matches!(Book::from_bytes(&[0; 20]), Err(BookError::Truncated(20))));
+    assert!(Book::from_bytes(&[]).unwrap().is_empty());
+}
+
+const GAMES: &str = "
+[Result \"1-0\"]
+1. e4 e5 2. Nf3 Nc6 1-0
+
+[Result \"0-1\"]
+1. e4 c5 2. Nf3 0-1
+
+[Result \"1/2-1/2\"]
+1. d4 d5 1/2-1/2
+";
+
+fn built(builder: &mut BookBuilder) -> Book{
+    for game in PgnGame::parse_all(GAMES).unwrap(){
+        builder.add_game(&game);
+    }
+    // Round trip through the file format on the way
+    Book::from_bytes(&builder.build().to_bytes()).unwrap()
+}
+
+fn book_moves(book: &Book, game: &Game) -> Vec<(String, u16)>{
+    book.moves(game).into_iter().map(|(m, w)| (m.to_uci(), w)).collect()
+}
+
+#[test]
+fn builds_from_games(){
+    let book = built(&mut BookBuilder::new());
+    let start = Game::default();
+    assert_eq!(book_moves(&book, &start), [("e2e4".to_owned(), 2), ("d2d4".to_owned(), 1)]);
+
+    let after_e4 = start.after_move(start.parse_uci_move("e2e4").unwrap());
+    assert_eq!(book.moves(&after_e4).len(), 2);
+    assert_eq!(book.len(), 8);
+}
+
+#[test]
+fn builds_with_filters(){
+    let start = Game::default();
+    let after_e4 = start.after_move(start.parse_uci_move("e2e4").unwrap());
+
+    let mut builder = BookBuilder::new();
+    builder.max_ply = Some(1);
+    let book = built(&mut builder);
+    assert_eq!(book.len(), 2);
+    assert!(book.moves(&after_e4).is_empty());
+
+    let mut builder = BookBuilder::new();
+    builder.min_count = 2;
+    let book = built(&mut builder);
+    assert_eq!(book_moves(&book, &start), [("e2e4".to_owned(), 2)]);
+    assert_eq!(book.len(), 1);
+
+    // Wins score two and draws one, for the side making the move
+    let mut builder = BookBuilder::new();
+    builder.weight_by_result = true;
+    let book = built(&mut builder);
+    assert_eq!(book_moves(&book, &start), [("e2e4".to_owned(), 2), ("d2d4".to_owned(), 1)]);
+    assert_eq!(book_moves(&book, &after_e4), [("c7c5".to_owned(), 2), ("e7e5".to_owned(), 0)]);
+}
@@ -0,0 +1,98 @@
+use libchess::board::ChessBoard;
+use libchess::eval::{Evaluator, MaterialEvaluator, TaperedEvaluator};
+use libchess::piece::Team;
+
+fn board(placement: &str) -> ChessBoard{
+    ChessBoard::from_fen(placement).unwrap()
+}
+
+///
+/// Swap the colours and flip the board vertically
+///
+fn mirror(placement: &str) -> String{
+    placement
+        .split('/')
+        .rev()
+        .map(|rank| rank.chars().map(|c| if c.is_ascii_uppercase() { c.to_ascii_lowercase() } else { c.to_ascii_uppercase() }).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+const POSITIONS: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1",
+];
+
+#[test]
+fn start_position_is_balanced(){
+    let start = ChessBoard::default();
+    assert_eq!(TaperedEvaluator.evaluate(&start, Team::White), 0);
+    assert_eq!(MaterialEvaluator.evaluate(&start, Team::Black), 0);
+}
+
+#[test]
+fn symmetric_between_teams(){
+    for placement in POSITIONS{
+        let original = board(placement);
+        let mirrored = board(&mirror(placement));
+
+        for evaluator in [&TaperedEvaluator as &dyn Evaluator, &MaterialEvaluator]{
+            let white = evaluator.evaluate(&original, Team::White);
+            assert_eq!(white, -evaluator.evaluate(&original, Team::Black), "{placement}");
+            assert_eq!(white, evaluator.evaluate(&mirrored, Team::Black), "{placement}");
+        }
+    }
+}
+
+#[test]
+fn material_counts(){
+    // White is a knight up
+    let b = board("rnbqkb1r/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR");
+    assert_eq!(MaterialEvaluator.evaluate(&b, Team::White), 320);
+    assert!(TaperedEvaluator.evaluate(&b, Team::White) > 200);
+}
+
+#[test]
+fn rewards_passed_pawns(){
+    let advanced = board("4k3/8/3P4/8/8/8/8/4K3");
+    let home = board("4k3/8/8/8/8/3P4/8/4K3");
+    assert!(TaperedEvaluator.evaluate(&advanced, Team::White) > TaperedEvaluator.evaluate(&home, Team::White));
+
+    // The same pawn stops being passed once an enemy pawn guards its path
+    let passed = board("4k3/p7/8/3P4/8/8/8/4K3");
+    let guarded = board("4k3/4p3/8/3P4/8/8/8/4K3");
+    assert!(TaperedEvaluator.evaluate(&passed, Team::White) > TaperedEvaluator.evaluate(&guarded, Team::White));
+}
+
+#[test]
+fn penalises_weak_pawns(){
+    let healthy = board("4k3/pppp4/8/8/8/8/PPPP4/4K3");
+    let doubled = board("4k3/pppp4/8/8/8/2P5/P1PP4/4K3");
+    let isolated = board("4k3/pppp4/8/8/8/8/P1PP4/4K3");
+
+    let healthy = TaperedEvaluator.evaluate(&healthy, Team::White);
+    assert!(healthy > TaperedEvaluator.evaluate(&doubled, Team::White));
+    assert!(healthy - 100 > TaperedEvaluator.evaluate(&isolated, Team::White));
+}
+
+#[test]
+fn king_placement_depends_on_phase(){
+    // With the queens on, the king belongs behind its pawns
+    let castled = board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1RK1");
+    let wandering = board("rnbqkbnr/pppppppp/8/8/4K3/8/PPPPPPPP/RNBQ1R2");
+    assert!(TaperedEvaluator.evaluate(&castled, Team::White) > TaperedEvaluator.evaluate(&wandering, Team::White));
+
+    // In a bare king ending it belongs in the centre
+    let cornered = board("4k3/8/8/8/8/8/8/K7");
+    let central = board("4k3/8/8/8/3K4/8/8/8");
+    assert!(TaperedEvaluator.evaluate(&central, Team::White) > TaperedEvaluator.evaluate(&cornered, Team::White));
+}
+
+#[test]
+fn rewards_mobility(){
+    let free = board("4k3/8/8/8/3B4/8/1P6/4K3");
+    let hemmed = board("4k3/8/8/8/8/8/1P6/B3K3");
+    assert!(TaperedEvaluator.evaluate(&free, Team::White) > TaperedEvaluator.evaluate(&hemmed, Team::White));
+}
@@ -0,0 +1,77 @@
+use libchess::game::{FenParseError, Game, GameState};
+use libchess::piece::Team;
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[test]
+fn default_matches_start_position(){
+    assert_eq!(Game::default().to_fen_str(), START_FEN);
+}
+
+#[test]
+fn round_trip(){
+    let fens = [
+        START_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w Kq d6 0 3",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+    ];
+
+    for fen in fens{
+        let game = Game::from_fen_str(fen).unwrap();
+        assert_eq!(game.to_fen_str(), fen);
+    }
+}
+
+#[test]
+fn optional_move_counters(){
+    let game = Game::from_fen_str("8/8/8/4k3/8/8/8/4K3 b - -").unwrap();
+    assert_eq!(game.state, GameState::BlackToMove);
+    assert_eq!(game.halfmove_num, 0);
+    assert_eq!(game.fullmove_num, 1);
+}
+
+#[test]
+fn fullmove_increments_after_black(){
+    let mut game = Game::default();
+    let mut mb = Vec::new();
+
+    let mov = game.get_all_moves(&mut mb).unwrap()[0];
+    game.make_move(mov);
+    assert_eq!(game.fullmove_num, 1);
+
+    mb.clear();
+    let mov = game.get_all_moves(&mut mb).unwrap()[0];
+    game.make_move(mov);
+    assert_eq!(game.fullmove_num, 2);
+}
+
+#[test]
+fn malformed_fields(){
+    let cases = [
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1", FenParseError::BadRankCount(7)),
+        ("rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenParseError::BadRankLength { rank: 7 }),
+        ("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenParseError::BadRankLength { rank: 6 }),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNRR w KQkq - 0 1", FenParseError::BadRankLength { rank: 1 }),
+        // Enough empty squares to wrap a byte, were they summed unchecked
+        ("rnbqkbnr/pppppppp/88888888888888888888888888888888/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenParseError::BadRankLength { rank: 6 }),
+        ("rnbqkbnr/pppppppp/45/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenParseError::BadRankLength { rank: 6 }),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQXBNR w KQkq - 0 1", FenParseError::UnknownPiece('X')),
+        ("rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1", FenParseError::BadKingCount { team: Team::Black, count: 0 }),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1", FenParseError::BadSideToMove("x".to_owned())),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1", FenParseError::BadCastling('x')),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KKq - 0 1", FenParseError::BadCastling('K')),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1", FenParseError::BadEnPassant("e3".to_owned())),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq z9 0 1", FenParseError::BadEnPassant("z9".to_owned())),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - a 1", FenParseError::BadHalfmoveClock("a".to_owned())),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0", FenParseError::BadFullmoveNumber("0".to_owned())),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w", FenParseError::BadFieldCount(2)),
+    ];
+
+    for (fen, err) in cases{
+        assert_eq!(Game::from_fen_str(fen).unwrap_err(), err, "{fen}");
+    }
+}
@@ -0,0 +1,160 @@
+use libchess::game::{DrawReason, Game, GameMove, GameState};
+use libchess::piece::Team;
+use positioning::Position;
+
+fn play(game: &mut Game, from: &str, to: &str){
+    let from = Position::from_alphanum(from).unwrap();
+    let to = Position::from_alphanum(to).unwrap();
+
+    let mut mb = Vec::new();
+    let mov = *game
+        .get_all_moves(&mut mb)
+        .unwrap()
+        .iter()
+        .find(|m| match m {
+            GameMove::Regular(r) | GameMove::Capture(r) => r.from == from && r.to == to,
+            _ => false,
+        })
+        .expect("Move is legal");
+    game.make_move(mov);
+}
+
+#[test]
+fn fools_mate(){
+    let mut game = Game::default();
+    play(&mut game, "f2", "f3");
+    play(&mut game, "e7", "e5");
+    play(&mut game, "g2", "g4");
+    play(&mut game, "d8", "h4");
+    assert_eq!(game.state, GameState::BlackVictory);
+}
+
+#[test]
+fn back_rank_mate(){
+    let mut game = Game::from_fen_str("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    play(&mut game, "a1", "a8");
+    assert_eq!(game.state, GameState::WhiteVictory);
+}
+
+#[test]
+fn check_escaped_by_block(){
+    // The king has no squares, but the bishop can interpose on f8
+    let mut game = Game::from_fen_str("6k1/5ppp/8/8/1b6/8/8/R5K1 w - - 0 1").unwrap();
+    play(&mut game, "a1", "a8");
+    assert_eq!(game.state, GameState::BlackToMove);
+    assert!(game.is_in_check());
+}
+
+#[test]
+fn check_escaped_by_capture(){
+    // The king has no squares, but the rook on d8 can take the checker
+    let mut game = Game::from_fen_str("3r2k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    play(&mut game, "a1", "a8");
+    assert_eq!(game.state, GameState::BlackToMove);
+}
+
+#[test]
+fn stalemate(){
+    let mut game = Game::from_fen_str("7k/8/5K2/8/8/8/8/6Q1 w - - 0 1").unwrap();
+    play(&mut game, "g1", "g6");
+    assert_eq!(game.state, GameState::Draw { reason: DrawReason::Stalemate, to_move: Team::Black });
+    assert!(!game.is_in_check());
+}
+
+fn shuffle_knights(game: &mut Game){
+    play(game, "g1", "f3");
+    play(game, "g8", "f6");
+    play(game, "f3", "g1");
+    play(game, "f6", "g8");
+}
+
+#[test]
+fn threefold_repetition_is_claimable(){
+    let mut game = Game::default();
+    assert_eq!(game.claimable_draw(), None);
+
+    shuffle_knights(&mut game);
+    assert_eq!(game.repetition_count(), 2);
+    assert_eq!(game.claimable_draw(), None);
+
+    shuffle_knights(&mut game);
+    assert_eq!(game.repetition_count(), 3);
+    assert_eq!(game.state, GameState::WhiteToMove);
+    assert_eq!(game.claim_draw(), Some(DrawReason::ThreefoldRepetition));
+    assert_eq!(game.state, GameState::Draw { reason: DrawReason::ThreefoldRepetition, to_move: Team::White });
+}
+
+#[test]
+fn fivefold_repetition(){
+    let mut game = Game::default();
+    for _ in 0..4 {
+        shuffle_knights(&mut game);
+    }
+    assert_eq!(game.state, GameState::Draw { reason: DrawReason::FivefoldRepetition, to_move: Team::White });
+}
+
+#[test]
+fn pawn_move_resets_repetitions(){
+    let mut game = Game::default();
+    shuffle_knights(&mut game);
+    play(&mut game, "e2", "e4");
+    play(&mut game, "e7", "e5");
+    shuffle_knights(&mut game);
+    assert_eq!(game.repetition_count(), 2);
+}
+
+#[test]
+fn moves_after_the_game_ends_resume_play(){
+    let mut game = Game::default();
+    for _ in 0..4 {
+        shuffle_knights(&mut game);
+    }
+    assert!(game.state.is_finished());
+    let mov = game.legal_moves()[0];
+    game.make_move(mov);
+    assert_eq!(game.state, GameState::BlackToMove);
+}
+
+#[test]
+fn fifty_and_seventy_five_move_rules(){
+    let mut game = Game::from_fen_str("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80").unwrap();
+    assert_eq!(game.claimable_draw(), None);
+    play(&mut game, "a1", "a2");
+    assert_eq!(game.claimable_draw(), Some(DrawReason::FiftyMoveRule));
+    assert_eq!(game.state, GameState::BlackToMove);
+
+    let mut game = Game::from_fen_str("4k3/8/8/8/8/8/4P3/R3K3 w - - 149 80").unwrap();
+    play(&mut game, "a1", "a2");
+    assert_eq!(game.state, GameState::Draw { reason: DrawReason::SeventyFiveMoveRule, to_move: Team::Black });
+}
+
+#[test]
+fn checkmate_beats_seventy_five_move_rule(){
+    let mut game = Game::from_fen_str("6k1/5ppp/8/8/8/8/8/R5K1 w - - 149 80").unwrap();
+    play(&mut game, "a1", "a8");
+    assert_eq!(game.state, GameState::WhiteVictory);
+}
+
+#[test]
+fn insufficient_material(){
+    // Capturing the last rook leaves a lone king and knight
+    let mut game = Game::from_fen_str("4k3/8/8/8/8/8/3r4/2N1K3 w - - 0 1").unwrap();
+    play(&mut game, "e1", "d2");
+    assert_eq!(game.state, GameState::Draw { reason: DrawReason::InsufficientMaterial, to_move: Team::Black });
+
+    // Bishops on the same colour cannot mate
+    let game = Game::from_fen_str("4k3/8/3b4/8/8/8/8/2B1K3 w - - 0 1").unwrap();
+    assert!(game.board.has_insufficient_material());
+
+    // Bishops on opposite colours could
+    let game = Game::from_fen_str("4k3/8/2b5/8/8/8/8/2B1K3 w - - 0 1").unwrap();
+    assert!(!game.board.has_insufficient_material());
+}
+
+#[test]
+fn agreement(){
+    let mut game = Game::default();
+    game.agree_draw();
+    assert_eq!(game.state, GameState::Draw { reason: DrawReason::Agreement, to_move: Team::White });
+    assert_eq!(game.to_fen_str(), Game::default().to_fen_str());
+}
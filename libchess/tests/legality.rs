@@ -0,0 +1,81 @@
+use libchess::board::ChessBoard;
+use libchess::game::Game;
+use libchess::piece::*;
+use positioning::Position;
+
+fn check_kind<T: TTeam, K: TPieceKind>(board: &ChessBoard, fen: &str) {
+    let masks = board.legality_masks::<T>();
+    for pos in board.pieces::<T, K>().positions() {
+        assert_eq!(
+            board.get_piece_masked_moves::<T, K>(pos, &masks),
+            board.get_piece_legal_moves::<T, K>(pos),
+            "Moves of {:?} {:?} on {pos} in {fen}",
+            T::TEAM,
+            K::KIND
+        );
+    }
+}
+
+fn check_team<T: TTeam>(board: &ChessBoard, fen: &str) {
+    check_kind::<T, GPawn>(board, fen);
+    check_kind::<T, GKnight>(board, fen);
+    check_kind::<T, GBishop>(board, fen);
+    check_kind::<T, GRook>(board, fen);
+    check_kind::<T, GQueen>(board, fen);
+    check_kind::<T, GKing>(board, fen);
+}
+
+///
+/// Walk the move tree, checking the masked moves of every
+/// piece agree with playing each move out on a board copy
+///
+fn check_parity(game: &mut Game, depth: u32) {
+    let fen = game.to_fen_str();
+    match game.side_to_move() {
+        Team::White => check_team::<GWhite>(&game.board, &fen),
+        Team::Black => check_team::<GBlack>(&game.board, &fen),
+    }
+    if depth == 0 {
+        return;
+    }
+
+    let mut mb = Vec::new();
+    for &mov in game.get_legal_moves(&mut mb).iter() {
+        let undo = game.make_move(mov);
+        check_parity(game, depth - 1);
+        game.unmake_move(mov, undo);
+    }
+}
+
+#[test]
+fn masked_moves_match_reference() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    ];
+    for fen in fens {
+        check_parity(&mut Game::from_fen_str(fen).unwrap(), 2);
+    }
+}
+
+#[test]
+fn pins_and_checks() {
+    // The e2 knight is pinned, and the a5 bishop gives check
+    let game = Game::from_fen_str("4r1k1/8/8/b7/8/8/4N3/4K3 w - - 0 1").unwrap();
+    let masks = game.board.legality_masks::<GWhite>();
+
+    let sq = |s| Position::from_alphanum(s).unwrap();
+    assert_eq!(masks.king, sq("e1"));
+    assert_eq!(masks.checkers.positions().collect::<Vec<_>>(), [sq("a5")]);
+    assert_eq!(masks.pinned.positions().collect::<Vec<_>>(), [sq("e2")]);
+    assert_eq!(masks.check_mask.count(), 4);
+    assert!(game
+        .board
+        .get_piece_masked_moves::<GWhite, GKnight>(sq("e2"), &masks)
+        .empty());
+    assert!(!(masks.king_danger & positioning::Bitboard::from(sq("e2"))).empty());
+}
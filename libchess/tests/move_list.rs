@@ -0,0 +1,63 @@
+use libchess::game::{Game, GameMove, MoveGenerationError};
+use libchess::move_list::{MoveList, MOVE_LIST_CAPACITY};
+
+#[test]
+fn matches_vec_generation() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+    ];
+    for fen in fens {
+        let game = Game::from_fen_str(fen).unwrap();
+        let mut mb = Vec::new();
+        assert_eq!(&*game.legal_moves(), game.get_legal_moves(&mut mb), "{fen}");
+        assert_eq!(game.moves().unwrap(), game.legal_moves(), "{fen}");
+    }
+}
+
+#[test]
+fn finished_games_have_no_moves() {
+    // Fool's mate
+    let mut game = Game::default();
+    for uci in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+        game.make_move(game.parse_uci_move(uci).unwrap());
+    }
+    assert!(matches!(game.moves(), Err(MoveGenerationError::GameFinished(_))));
+    assert!(game.legal_moves().is_empty());
+}
+
+#[test]
+fn list_operations() {
+    let game = Game::default();
+    let mut moves = game.legal_moves();
+    assert_eq!(moves.len(), 20);
+
+    moves.retain(|m| m.from_pos().row() == 1);
+    assert_eq!(moves.len(), 16);
+
+    moves.sort_by_key(|m| std::cmp::Reverse(m.to_pos().integral()));
+    assert!(moves.windows(2).all(|w| w[0].to_pos().integral() >= w[1].to_pos().integral()));
+
+    let last = moves[moves.len() - 1];
+    assert_eq!(moves.pop(), Some(last));
+    assert_eq!(moves.len(), 15);
+
+    let owned: Vec<GameMove> = moves.clone().into_iter().collect();
+    assert_eq!(owned, &*moves);
+    assert_eq!(owned.into_iter().collect::<MoveList>(), moves);
+
+    moves.clear();
+    assert!(moves.is_empty());
+    assert_eq!(moves.pop(), None);
+}
+
+#[test]
+fn holds_its_capacity() {
+    let mov = Game::default().legal_moves()[0];
+    let mut moves = MoveList::new();
+    for _ in 0..MOVE_LIST_CAPACITY {
+        moves.push(mov);
+    }
+    assert_eq!(moves.len(), MOVE_LIST_CAPACITY);
+}
@@ -0,0 +1,95 @@
+use libchess::game::{Game, Promotion};
+use libchess::packed_move::{PackedKind, PackedMove};
+use positioning::Position;
+
+///
+/// Walk the move tree, checking every legal move
+/// survives packing and is accepted by the validators
+///
+fn check_roundtrip(game: &mut Game, depth: u32) {
+    if depth == 0 {
+        return;
+    }
+    for mov in game.legal_moves() {
+        let packed = PackedMove::from(mov);
+        assert_eq!(packed.from_pos(), mov.from_pos(), "{mov:?}");
+        assert_eq!(packed.to_pos(), mov.to_pos(), "{mov:?}");
+        assert_eq!(packed.promotion(), mov.promotion(), "{mov:?}");
+        assert_eq!(game.unpack_move(packed), Some(mov), "{mov:?}");
+        assert!(game.is_pseudo_legal(packed), "{mov:?}");
+        assert!(game.is_legal(packed), "{mov:?}");
+
+        let undo = game.make_move(mov);
+        check_roundtrip(game, depth - 1);
+        game.unmake_move(mov, undo);
+    }
+}
+
+fn packed(from: &str, to: &str, kind: PackedKind, promotion: Option<Promotion>) -> PackedMove {
+    let sq = |s| Position::from_alphanum(s).unwrap();
+    PackedMove::new(sq(from), sq(to), kind, promotion)
+}
+
+#[test]
+fn is_compact() {
+    assert_eq!(std::mem::size_of::<PackedMove>(), 2);
+}
+
+#[test]
+fn legal_moves_roundtrip() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ];
+    for fen in fens {
+        check_roundtrip(&mut Game::from_fen_str(fen).unwrap(), 3);
+    }
+}
+
+#[test]
+fn rejects_moves_of_other_positions() {
+    let game = Game::default();
+    assert!(!game.is_legal(PackedMove::NULL));
+    assert_eq!(game.unpack_move(PackedMove::NULL), None);
+
+    // Nothing on e4, and a black pawn with white to move
+    assert!(!game.is_legal(packed("e4", "e5", PackedKind::Normal, None)));
+    assert!(!game.is_legal(packed("e7", "e5", PackedKind::Normal, None)));
+
+    // Onto a friendly piece, and too far
+    assert!(!game.is_legal(packed("d1", "d2", PackedKind::Normal, None)));
+    assert!(!game.is_pseudo_legal(packed("e2", "e5", PackedKind::Normal, None)));
+
+    // The right squares with the wrong kind of move
+    assert!(!game.is_legal(packed("e2", "e4", PackedKind::Enpassant, None)));
+    assert!(!game.is_legal(packed("e2", "e4", PackedKind::Castle, None)));
+    assert!(game.is_legal(packed("e2", "e4", PackedKind::Normal, None)));
+}
+
+#[test]
+fn promotions_must_be_flagged() {
+    let game = Game::from_fen_str("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    assert!(!game.is_pseudo_legal(packed("b7", "b8", PackedKind::Normal, None)));
+    assert!(game.is_legal(packed("b7", "b8", PackedKind::Promotion, Some(Promotion::Knight))));
+    assert!(!game.is_legal(packed("e1", "e2", PackedKind::Promotion, Some(Promotion::Queen))));
+}
+
+#[test]
+fn pseudo_legal_moves_may_leave_check() {
+    // The d2 knight is pinned by the b4 bishop,
+    // and the f3 rook stops kingside castling
+    let game = Game::from_fen_str("4k3/8/8/8/1b6/5r2/3N4/R3K2R w KQ - 0 1").unwrap();
+
+    let pinned = packed("d2", "f1", PackedKind::Normal, None);
+    assert!(game.is_pseudo_legal(pinned));
+    assert!(!game.is_legal(pinned));
+
+    let kingside = packed("e1", "g1", PackedKind::Castle, None);
+    assert!(game.is_pseudo_legal(kingside));
+    assert!(!game.is_legal(kingside));
+
+    let queenside = packed("e1", "c1", PackedKind::Castle, None);
+    assert!(game.is_legal(queenside));
+}
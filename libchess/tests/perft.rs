@@ -5,7 +5,7 @@ fn perft_0(){
         0
     );
     assert_eq!(data.enpassant, 0);
-    assert_eq!(data.nodes, 0);
+    assert_eq!(data.nodes, 1);
     assert_eq!(data.captures, 0);
     assert_eq!(data.castles, 0);
     assert_eq!(data.promotions, 0);
@@ -48,6 +48,8 @@ fn perft_3(){
     assert_eq!(data.captures, 34);
     assert_eq!(data.castles, 0);
     assert_eq!(data.promotions, 0);
+    assert_eq!(data.checks, 12);
+    assert_eq!(data.checkmates, 0);
 }
 
 #[test]
@@ -61,4 +63,143 @@ fn perft_4(){
     assert_eq!(data.captures, 1576);
     assert_eq!(data.castles, 0);
     assert_eq!(data.promotions, 0);
+    assert_eq!(data.checks, 469);
+    assert_eq!(data.discovery_checks, 0);
+    assert_eq!(data.checkmates, 8);
+}
+
+#[test]
+fn kiwipete_3(){
+    let game = libchess::game::Game::from_fen_str("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    let data = libchess_perft::perft(game, 3);
+    assert_eq!(data.nodes, 97862);
+    assert_eq!(data.captures, 17102);
+    assert_eq!(data.enpassant, 45);
+    assert_eq!(data.castles, 3162);
+    assert_eq!(data.promotions, 0);
+    assert_eq!(data.checks, 993);
+    assert_eq!(data.discovery_checks, 0);
+    assert_eq!(data.double_checks, 0);
+    assert_eq!(data.checkmates, 1);
+}
+
+#[test]
+fn position_3_5(){
+    // Rich in discovered checks along the fourth and fifth ranks
+    let game = libchess::game::Game::from_fen_str("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
+    let data = libchess_perft::perft(game, 5);
+    assert_eq!(data.nodes, 674624);
+    assert_eq!(data.captures, 52051);
+    assert_eq!(data.enpassant, 1165);
+    assert_eq!(data.checks, 52950);
+    assert_eq!(data.discovery_checks, 1292);
+    assert_eq!(data.double_checks, 3);
+    assert_eq!(data.checkmates, 0);
+}
+
+#[test]
+fn promotions_3(){
+    // Promotions with and without captures, for both sides
+    let game = libchess::game::Game::from_fen_str("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap();
+    let data = libchess_perft::perft(game.clone(), 1);
+    assert_eq!(data.nodes, 6);
+    assert_eq!(data.promotions, 0);
+
+    let data = libchess_perft::perft(game.clone(), 2);
+    assert_eq!(data.nodes, 264);
+    assert_eq!(data.captures, 87);
+    assert_eq!(data.castles, 6);
+    assert_eq!(data.promotions, 48);
+    assert_eq!(data.checks, 10);
+
+    let data = libchess_perft::perft(game, 3);
+    assert_eq!(data.nodes, 9467);
+    assert_eq!(data.captures, 1021);
+    assert_eq!(data.enpassant, 4);
+    assert_eq!(data.promotions, 120);
+    assert_eq!(data.checks, 38);
+    assert_eq!(data.checkmates, 22);
+}
+
+#[test]
+fn promotions_only_as_promotions(){
+    // Every pawn move here reaches the last row, so each has four promotions and nothing else
+    let game = libchess::game::Game::from_fen_str("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap();
+    let mut mb = Vec::new();
+    let pawn_moves: Vec<_> = game
+        .get_legal_moves(&mut mb)
+        .iter()
+        .filter(|m| m.piece_kind() == libchess::piece::PieceKind::Pawn)
+        .collect();
+    assert!(pawn_moves.iter().all(|m| m.promotion().is_some()), "{pawn_moves:?}");
+
+    assert_eq!(libchess_perft::count_nodes(game.clone(), 1), 24);
+    assert_eq!(libchess_perft::count_nodes(game.clone(), 3), 9483);
+    assert_eq!(libchess_perft::count_nodes(game, 4), 182838);
+
+    let game = libchess::game::Game::from_fen_str("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap();
+    assert_eq!(libchess_perft::count_nodes(game.clone(), 1), 44);
+    assert_eq!(libchess_perft::count_nodes(game.clone(), 2), 1486);
+    assert_eq!(libchess_perft::count_nodes(game, 3), 62379);
+}
+
+#[test]
+fn divide_3(){
+    let counts = libchess_perft::divide(&libchess::game::Game::default(), 3);
+    assert_eq!(counts.len(), 20);
+    assert_eq!(counts.iter().map(|(_, n)| n).sum::<u64>(), 8902);
+
+    let nodes_after = |uci: &str| counts.iter().find(|(m, _)| m.to_uci() == uci).unwrap().1;
+    assert_eq!(nodes_after("e2e4"), 600);
+    assert_eq!(nodes_after("g1f3"), 440);
+}
+
+const STANDARD_SUITE: &str = include_str!("../../perft/suites/standard.epd");
+
+#[test]
+fn parses_epd_suites(){
+    let positions = libchess_perft::epd::parse_epd(STANDARD_SUITE).unwrap();
+    assert_eq!(positions.len(), 7);
+    assert_eq!(positions[0].fen, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -");
+    assert_eq!(positions[0].expected[..3], [(1, 20), (2, 400), (3, 8902)]);
+
+    let comments = "# A comment\n\n8/8/8/8/8/8/8/K1k5 w - - ;D1 3\n";
+    assert_eq!(libchess_perft::epd::parse_epd(comments).unwrap()[0].expected, [(1, 3)]);
+
+    assert!(libchess_perft::epd::parse_epd("8/8/8/8/8/8/8/8 w - - ;D1 0").is_err());
+    assert!(libchess_perft::epd::parse_epd("8/8/8/8/8/8/8/K1k5 w - - ;D1").is_err());
+    assert!(libchess_perft::epd::parse_epd("8/8/8/8/8/8/8/K1k5 w - - ;X1 3").is_err());
+}
+
+#[test]
+fn runs_epd_suites(){
+    let positions = libchess_perft::epd::parse_epd(STANDARD_SUITE).unwrap();
+    let results = libchess_perft::epd::run_suite(&positions, 3);
+
+    assert_eq!(results.len(), 7);
+    for result in results{
+        assert_eq!(result.len(), 3);
+        assert!(result.iter().all(libchess_perft::epd::DepthResult::passed), "{result:?}");
+    }
+
+    // A wrong count is reported as such
+    let wrong = libchess_perft::epd::parse_epd("8/8/8/8/8/8/8/K1k5 w - - ;D1 3").unwrap();
+    let result = libchess_perft::epd::run_position(&wrong[0], 5);
+    assert!(!result[0].passed());
+    assert_eq!(result[0].actual, 1);
+}
+
+#[test]
+fn bulk_counting_agrees(){
+    for (fen, depth, nodes) in [
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 4, 197281),
+        ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3, 97862),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 5, 674624),
+    ]{
+        let game = libchess::game::Game::from_fen_str(fen).unwrap();
+        assert_eq!(libchess_perft::count_nodes(game.clone(), depth), nodes, "{fen}");
+        assert_eq!(libchess_perft::count_nodes(game.clone(), 1), libchess_perft::perft(game, 1).nodes, "{fen}");
+    }
+    // The position itself is the one node at depth zero
+    assert_eq!(libchess_perft::count_nodes(libchess::game::Game::default(), 0), 1);
 }
@@ -0,0 +1,115 @@
+use libchess::game::{Game, GameState};
+use libchess::pgn::{PgnGame, PgnParseError};
+use libchess::san::SanParseError;
+
+const ANNOTATED: &str = r#"[Event "Casual Game"]
+[Site "Berlin GER"]
+[Date "1852.??.??"]
+[Round "?"]
+[White "Adolf Anderssen"]
+[Black "Jean Dufresne"]
+[Result "1-0"]
+[ECO "C52"]
+
+{The Evergreen game} 1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. b4!? Bxb4 5. c3 Ba5 6. d4
+exd4 7. O-O d3 $6 (7... Nge7 {is more solid} 8. Ng5) 8. Qb3 Qf6 9. e5 Qg6 10.
+Re1 Nge7 11. Ba3 b5 12. Qxb5 Rb8 13. Qa4 Bb6 14. Nbd2 Bb7 15. Ne4 Qf5 16. Bxd3
+Qh5 17. Nf6+ gxf6 18. exf6 Rg8 19. Rad1 Qxf3 20. Rxe7+ Nxe7 21. Qxd7+ Kxd7 22.
+Bf5+ Ke8 23. Bd7+ Kf8 24. Bxe7# 1-0
+"#;
+
+#[test]
+fn reads_annotated_game(){
+    let game = PgnGame::parse(ANNOTATED).unwrap();
+
+    assert_eq!(game.tag("White"), Some("Adolf Anderssen"));
+    assert_eq!(game.tag("ECO"), Some("C52"));
+    assert_eq!(game.moves.len(), 47);
+
+    assert_eq!(game.moves[0].comment_before.as_deref(), Some("The Evergreen game"));
+    assert_eq!(game.moves[6].nags, vec![5]);
+
+    let d3 = &game.moves[13];
+    assert_eq!(d3.nags, vec![6]);
+    assert_eq!(d3.variations.len(), 1);
+    assert_eq!(d3.variations[0].len(), 2);
+    assert_eq!(d3.variations[0][0].comment.as_deref(), Some("is more solid"));
+
+    let end = game.final_position();
+    assert_eq!(end.state, GameState::WhiteVictory);
+    assert_eq!(game.result(), "1-0");
+}
+
+#[test]
+fn round_trip(){
+    let game = PgnGame::parse(ANNOTATED).unwrap();
+    let written = game.to_string();
+    assert!(written.lines().all(|l| l.len() <= 80), "{written}");
+    assert!(written.contains("7. O-O d3 $6 (7... Nge7 {is more solid} 8. Ng5) 8. Qb3"), "{written}");
+
+    let reread = PgnGame::parse(&written).unwrap();
+    assert_eq!(reread.tags, game.tags);
+    assert_eq!(reread.moves, game.moves);
+    assert_eq!(reread.to_string(), written);
+}
+
+#[test]
+fn writes_seven_tag_roster(){
+    let mut game = PgnGame::default();
+    let mut pos = Game::default();
+    for san in ["f3", "e5", "g4", "Qh4#"]{
+        let mov = pos.parse_san_move(san).unwrap();
+        game.push_move(mov);
+        pos = pos.after_move(mov);
+    }
+    game.set_tag("White", "Fool");
+
+    assert_eq!(game.to_string(), "\
+[Event \"?\"]
+[Site \"?\"]
+[Date \"????.??.??\"]
+[Round \"?\"]
+[White \"Fool\"]
+[Black \"?\"]
+[Result \"0-1\"]
+
+1. f3 e5 2. g4 Qh4# 0-1
+");
+}
+
+#[test]
+fn starts_from_fen_tag(){
+    let pgn = "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K3 b - - 0 40\"]\n\n40... Kd7 41. Ra7+ *";
+    let game = PgnGame::parse(pgn).unwrap();
+    assert_eq!(game.moves.len(), 2);
+    assert_eq!(game.result(), "*");
+
+    let written = game.to_string();
+    assert!(written.contains("[FEN \"4k3/8/8/8/8/8/8/R3K3 b - - 0 40\"]"), "{written}");
+    assert!(written.ends_with("40... Kd7 41. Ra7+ *\n"), "{written}");
+}
+
+#[test]
+fn reads_multiple_games(){
+    let pgn = "[Event \"A\"]\n\n1. e4 e5 1/2-1/2\n\n[Event \"B\"]\n\n1.d4 d5 ; queen's pawn\n2.c4 *\n";
+    let games = PgnGame::parse_all(pgn).unwrap();
+
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].tag("Result"), Some("1/2-1/2"));
+    assert_eq!(games[1].tag("Event"), Some("B"));
+    assert_eq!(games[1].moves.len(), 3);
+    assert_eq!(games[1].moves[1].comment.as_deref(), Some("queen's pawn"));
+}
+
+#[test]
+fn reports_errors(){
+    assert_eq!(PgnGame::parse("1. e4 {unfinished").unwrap_err(), PgnParseError::UnterminatedComment);
+    assert_eq!(PgnGame::parse("1. e4 (1. d4 *").unwrap_err(), PgnParseError::UnbalancedVariation);
+    assert_eq!(PgnGame::parse("1. e4 e5)").unwrap_err(), PgnParseError::UnbalancedVariation);
+    assert_eq!(PgnGame::parse("[Event \"A]").unwrap_err(), PgnParseError::BadTag("[Event \"A]".to_owned()));
+    assert_eq!(PgnGame::parse("").unwrap_err(), PgnParseError::Empty);
+    assert_eq!(
+        PgnGame::parse("1. e4 e5 2. Ke3 *").unwrap_err(),
+        PgnParseError::BadMove { san: "Ke3".to_owned(), reason: SanParseError::Illegal("Ke3".to_owned()) }
+    );
+}
@@ -0,0 +1,90 @@
+use libchess::game::Game;
+use libchess::san::SanParseError;
+
+fn san_of(fen: &str, uci: &str) -> String{
+    let game = Game::from_fen_str(fen).unwrap();
+    game.move_to_san(game.parse_uci_move(uci).unwrap())
+}
+
+#[test]
+fn writes_san(){
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(san_of(start, "e2e4"), "e4");
+    assert_eq!(san_of(start, "g1f3"), "Nf3");
+
+    // Captures, en passant and castling
+    let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    assert_eq!(san_of(kiwipete, "d5e6"), "dxe6");
+    assert_eq!(san_of(kiwipete, "e2a6"), "Bxa6");
+    assert_eq!(san_of(kiwipete, "e1g1"), "O-O");
+    assert_eq!(san_of(kiwipete, "e1c1"), "O-O-O");
+    assert_eq!(san_of("r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1", "b4a3"), "bxa3");
+
+    // Promotions
+    assert_eq!(san_of("8/1P2k3/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), "b8=Q");
+    assert_eq!(san_of("n3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8n"), "bxa8=N");
+
+    // Checks and mates
+    assert_eq!(san_of("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8+");
+    assert_eq!(san_of("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8#");
+}
+
+#[test]
+fn disambiguation(){
+    // Knights on b1 and f3 can both reach d2
+    let fen = "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1";
+    assert_eq!(san_of(fen, "b1d2"), "Nbd2");
+
+    // Rooks on e1 and e3 share a file
+    let fen = "7k/8/8/8/8/4R3/8/K3R3 w - - 0 1";
+    assert_eq!(san_of(fen, "e1e2"), "R1e2");
+
+    // Three queens need both file and rank
+    let fen = "2k5/8/8/8/Q7/8/8/Q2QK3 w - - 0 1";
+    assert_eq!(san_of(fen, "a1d4"), "Qa1d4");
+    assert_eq!(san_of(fen, "a4d4"), "Q4d4");
+    assert_eq!(san_of(fen, "d1d4"), "Qdd4");
+}
+
+#[test]
+fn round_trip_all_moves(){
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "2k5/8/8/8/Q7/8/8/Q2QK3 w - - 0 1",
+    ];
+    for fen in fens{
+        let game = Game::from_fen_str(fen).unwrap();
+        let mut mb = Vec::new();
+        for mov in game.get_all_moves(&mut mb).unwrap(){
+            let san = game.move_to_san(*mov);
+            assert_eq!(game.parse_san_move(&san), Ok(*mov), "{fen}: {san}");
+        }
+    }
+}
+
+#[test]
+fn parse_variants(){
+    let kiwipete = Game::from_fen_str("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    let ks = kiwipete.parse_uci_move("e1g1").unwrap();
+    assert_eq!(kiwipete.parse_san_move("0-0"), Ok(ks));
+    assert_eq!(kiwipete.parse_san_move("O-O"), Ok(ks));
+    assert_eq!(kiwipete.parse_san_move("Ba6"), kiwipete.parse_san_move("Bxa6"));
+    assert_eq!(kiwipete.parse_san_move("Bxa6!?"), Ok(kiwipete.parse_uci_move("e2a6").unwrap()));
+
+    let promo = Game::from_fen_str("8/1P2k3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let queen = promo.parse_uci_move("b7b8q").unwrap();
+    assert_eq!(promo.parse_san_move("b8=Q"), Ok(queen));
+    assert_eq!(promo.parse_san_move("b8Q"), Ok(queen));
+    assert_eq!(promo.parse_san_move("b8=Q+"), Ok(queen));
+}
+
+#[test]
+fn parse_errors(){
+    let game = Game::from_fen_str("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1").unwrap();
+    assert_eq!(game.parse_san_move("Nd2"), Err(SanParseError::Ambiguous("Nd2".to_owned())));
+    assert_eq!(game.parse_san_move("Nd3"), Err(SanParseError::Illegal("Nd3".to_owned())));
+    assert_eq!(game.parse_san_move("Nz9"), Err(SanParseError::Malformed("Nz9".to_owned())));
+    assert_eq!(game.parse_san_move("e8=K"), Err(SanParseError::Malformed("e8=K".to_owned())));
+}
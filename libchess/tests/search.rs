@@ -0,0 +1,103 @@
+use libchess::eval::TaperedEvaluator;
+use libchess::game::Game;
+use libchess::search::{search, Score, SearchLimits};
+use libchess::tt::TranspositionTable;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+fn search_depth(fen: &str, depth: u32) -> libchess::search::SearchReport{
+    let game = Game::from_fen_str(fen).unwrap();
+    let limits = SearchLimits { depth: Some(depth), ..Default::default() };
+    search(&game, &TaperedEvaluator, &TranspositionTable::new(1), limits, &AtomicBool::new(false), |_| {})
+}
+
+fn pv_uci(fen: &str, depth: u32) -> Vec<String>{
+    search_depth(fen, depth).pv.iter().map(|m| m.to_uci()).collect()
+}
+
+#[test]
+fn finds_mate_in_one(){
+    let report = search_depth("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2);
+    assert_eq!(report.score, Score::Mate(1));
+    assert_eq!(pv_uci("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2), ["a1a8"]);
+}
+
+#[test]
+fn finds_mate_in_two(){
+    // Checking straight away lets the king out via a7, 1. Kb6 has to come first
+    let report = search_depth("k7/8/2K5/8/8/8/8/7R w - - 0 1", 4);
+    assert_eq!(report.score, Score::Mate(2));
+    assert_eq!(report.pv.len(), 3);
+}
+
+#[test]
+fn sees_being_mated(){
+    let report = search_depth("8/8/8/8/8/5kq1/P7/7K w - - 0 1", 3);
+    assert_eq!(report.score, Score::Mate(-1), "{report:?}");
+}
+
+#[test]
+fn wins_hanging_material(){
+    assert_eq!(pv_uci("4k3/8/8/3q4/8/8/3R4/3K4 w - - 0 1", 1)[0], "d2d5");
+    assert_eq!(pv_uci("4k3/8/8/3q4/4P3/8/8/3K4 w - - 0 1", 2)[0], "e4d5");
+}
+
+#[test]
+fn avoids_defended_pieces(){
+    // The knight on d5 is protected by the pawn on e6, so taking it loses the queen
+    let report = search_depth("4k3/8/4p3/3n4/8/8/3Q4/3K4 w - - 0 1", 3);
+    assert_ne!(report.best_move().unwrap().to_uci(), "d2d5");
+}
+
+#[test]
+fn reports_every_iteration(){
+    let game = Game::default();
+    let limits = SearchLimits { depth: Some(4), ..Default::default() };
+    let mut depths = Vec::new();
+    let report = search(&game, &TaperedEvaluator, &TranspositionTable::new(1), limits, &AtomicBool::new(false), |r| depths.push(r.depth));
+
+    assert_eq!(depths, [1, 2, 3, 4]);
+    assert_eq!(report.depth, 4);
+    assert!(!report.pv.is_empty());
+}
+
+#[test]
+fn respects_limits(){
+    let game = Game::default();
+
+    let limits = SearchLimits { nodes: Some(5000), ..Default::default() };
+    let report = search(&game, &TaperedEvaluator, &TranspositionTable::new(1), limits, &AtomicBool::new(false), |_| {});
+    assert!(report.nodes <= 5000);
+    assert!(report.best_move().is_some());
+
+    let limits = SearchLimits { time: Some(Duration::from_millis(50)), ..Default::default() };
+    let report = search(&game, &TaperedEvaluator, &TranspositionTable::new(1), limits, &AtomicBool::new(false), |_| {});
+    assert!(report.elapsed < Duration::from_secs(1));
+    assert!(report.best_move().is_some());
+
+    // A raised stop flag still leaves a move to play
+    let report = search(&game, &TaperedEvaluator, &TranspositionTable::new(1), SearchLimits::default(), &AtomicBool::new(true), |_| {});
+    assert_eq!(report.depth, 0);
+    assert!(report.best_move().is_some());
+}
+
+#[test]
+fn finished_game_has_no_move(){
+    let report = search_depth("7k/5QQ1/8/8/8/8/8/K7 b - - 0 1", 3);
+    assert!(report.best_move().is_none());
+}
+
+#[test]
+fn reuses_transposition_table(){
+    let game = Game::from_fen_str("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    let tt = TranspositionTable::new(4);
+    let limits = SearchLimits { depth: Some(4), ..Default::default() };
+
+    let first = search(&game, &TaperedEvaluator, &tt, limits, &AtomicBool::new(false), |_| {});
+    assert!(tt.hashfull() > 0);
+
+    // A second search of the same position is answered largely from the table
+    let second = search(&game, &TaperedEvaluator, &tt, limits, &AtomicBool::new(false), |_| {});
+    assert_eq!(first.score, second.score);
+    assert!(second.nodes < first.nodes);
+}
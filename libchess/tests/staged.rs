@@ -0,0 +1,83 @@
+use libchess::game::{Game, GameMove};
+
+fn sorted(moves: &[GameMove]) -> Vec<String> {
+    let mut names: Vec<_> = moves.iter().map(|m| format!("{m:?}")).collect();
+    names.sort();
+    names
+}
+
+fn check_stages(game: &Game) {
+    let fen = game.to_fen_str();
+    let all = game.legal_moves();
+    let captures = game.capture_moves();
+    let quiets = game.quiet_moves();
+
+    // Every move shows up in exactly one of the captures and quiets
+    let union: Vec<_> = captures.iter().chain(quiets.iter()).copied().collect();
+    assert_eq!(sorted(&union), sorted(&all), "Captures and quiets in {fen}");
+
+    assert!(
+        captures.iter().all(|m| m.is_capture() || m.promotion().is_some()),
+        "Captures in {fen}"
+    );
+    assert!(
+        quiets.iter().all(|m| !m.is_capture() && m.promotion().is_none()),
+        "Quiets in {fen}"
+    );
+
+    let evasions = game.evasion_moves();
+    if game.is_in_check() {
+        assert_eq!(sorted(&evasions), sorted(&all), "Evasions in {fen}");
+    } else {
+        assert!(evasions.is_empty(), "Evasions in {fen}");
+    }
+
+    let checking: Vec<_> = quiets
+        .iter()
+        .copied()
+        .filter(|&m| game.after_move(m).is_in_check())
+        .collect();
+    assert_eq!(sorted(&game.quiet_checks()), sorted(&checking), "Quiet checks in {fen}");
+}
+
+fn walk(game: &mut Game, depth: u32) {
+    check_stages(game);
+    if depth == 0 {
+        return;
+    }
+    for mov in game.legal_moves() {
+        let undo = game.make_move(mov);
+        walk(game, depth - 1);
+        game.unmake_move(mov, undo);
+    }
+}
+
+#[test]
+fn stages_partition_legal_moves() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+    ];
+    for fen in fens {
+        walk(&mut Game::from_fen_str(fen).unwrap(), 2);
+    }
+}
+
+#[test]
+fn castling_and_discovered_checks() {
+    // Castling kingside puts the rook on f1, in line with the king on f8
+    let game = Game::from_fen_str("5k2/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    let checks: Vec<_> = game.quiet_checks().iter().map(GameMove::to_uci).collect();
+    assert!(checks.contains(&"e1g1".to_owned()), "{checks:?}");
+    assert!(checks.contains(&"h1h8".to_owned()), "{checks:?}");
+
+    // Any move of the d4 knight uncovers the a4 rook onto the h4 king
+    let game = Game::from_fen_str("8/8/8/8/R2N3k/8/8/4K3 w - - 0 1").unwrap();
+    let checks = game.quiet_checks();
+    assert_eq!(checks.iter().filter(|m| m.to_uci().starts_with("d4")).count(), 8);
+}
@@ -0,0 +1,73 @@
+use libchess::tt::{Bound, SearchEntry, TranspositionTable};
+use libchess::zobrist::{ZKey, ZobristHash};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn random_hashes(n: usize) -> Vec<ZobristHash>{
+    let mut rng = StdRng::seed_from_u64(7);
+    (0..n)
+        .map(|_| {
+            let mut hash = ZobristHash::default();
+            hash.update(ZKey::generate(&mut rng));
+            hash
+        })
+        .collect()
+}
+
+fn entry(depth: u8, score: i32) -> SearchEntry{
+    SearchEntry { depth, bound: Bound::Exact, score, best_move: None }
+}
+
+#[test]
+fn stores_and_probes(){
+    let tt = TranspositionTable::new(1);
+    let hashes = random_hashes(2);
+
+    assert_eq!(tt.probe(&hashes[0]), None);
+    tt.store(&hashes[0], entry(3, 42));
+    assert_eq!(tt.probe(&hashes[0]), Some(entry(3, 42)));
+    assert_eq!(tt.probe(&hashes[1]), None);
+
+    // The same position is always overwritten
+    tt.store(&hashes[0], entry(1, -7));
+    assert_eq!(tt.probe(&hashes[0]), Some(entry(1, -7)));
+}
+
+#[test]
+fn prefers_deep_and_fresh_entries(){
+    // A table of a single bucket, so every hash competes for the same slots
+    let tt = TranspositionTable::new(0);
+    let hashes = random_hashes(6);
+    let slots = tt.capacity();
+
+    for (depth, hash) in hashes[..slots].iter().enumerate(){
+        tt.store(hash, entry(depth as u8 + 1, 0));
+    }
+    tt.store(&hashes[slots], entry(10, 0));
+    assert_eq!(tt.probe(&hashes[0]), None, "The shallowest entry is replaced");
+    assert!(tt.probe(&hashes[slots]).is_some());
+
+    // Entries from earlier searches make way even when deeper
+    tt.new_search();
+    tt.store(&hashes[0], entry(1, 0));
+    assert!(tt.probe(&hashes[0]).is_some());
+}
+
+#[test]
+fn reports_hashfull(){
+    let mut tt = TranspositionTable::new(1);
+    assert_eq!(tt.hashfull(), 0);
+
+    for hash in random_hashes(tt.capacity() * 4){
+        tt.store(&hash, entry(1, 0));
+    }
+    assert!(tt.hashfull() > 900, "{}", tt.hashfull());
+
+    // Entries from an earlier search no longer count
+    tt.new_search();
+    assert_eq!(tt.hashfull(), 0);
+
+    tt.clear();
+    assert_eq!(tt.hashfull(), 0);
+    assert_eq!(tt.probe(&random_hashes(1)[0]), None);
+}
@@ -0,0 +1,44 @@
+use libchess::game::{Game, GameMove, Promotion, UciMoveError};
+use libchess::piece::Team;
+
+#[test]
+fn round_trip_all_moves(){
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    ];
+    for fen in fens{
+        let game = Game::from_fen_str(fen).unwrap();
+        let mut mb = Vec::new();
+        for mov in game.get_all_moves(&mut mb).unwrap(){
+            assert_eq!(game.parse_uci_move(&mov.to_uci()), Ok(*mov), "{fen}");
+        }
+    }
+}
+
+#[test]
+fn encoding(){
+    let game = Game::from_fen_str("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+    assert_eq!(game.parse_uci_move("e1g1"), Ok(GameMove::CastleKingside(Team::White)));
+    assert_eq!(game.parse_uci_move("e1c1"), Ok(GameMove::CastleQueenside(Team::White)));
+
+    let promo = game.parse_uci_move("b7b8n").unwrap();
+    assert_eq!(promo.promotion(), Some(Promotion::Knight));
+    assert_eq!(promo.to_uci(), "b7b8n");
+
+    let cap_promo = game.parse_uci_move("b7a8q").unwrap();
+    assert!(matches!(cap_promo, GameMove::CapturePromote { promotion: Promotion::Queen, .. }));
+}
+
+#[test]
+fn errors(){
+    let game = Game::default();
+    assert_eq!(game.parse_uci_move("e2e5"), Err(UciMoveError::Illegal("e2e5".to_owned())));
+    assert_eq!(game.parse_uci_move("e2e4q"), Err(UciMoveError::Illegal("e2e4q".to_owned())));
+    assert_eq!(game.parse_uci_move("e2"), Err(UciMoveError::Malformed("e2".to_owned())));
+    assert_eq!(game.parse_uci_move("i2i4"), Err(UciMoveError::Malformed("i2i4".to_owned())));
+    assert_eq!(game.parse_uci_move("e2e4k"), Err(UciMoveError::Malformed("e2e4k".to_owned())));
+}
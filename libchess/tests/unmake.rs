@@ -0,0 +1,77 @@
+use libchess::game::Game;
+
+///
+/// Walk the move tree, checking every move is
+/// exactly reverted by unmaking it
+///
+fn check_unmake(game: &mut Game, depth: u32){
+    if depth == 0 {
+        return;
+    }
+    let mut mb = Vec::new();
+    let moves = game.get_legal_moves(&mut mb);
+
+    for mov in moves{
+        let fen = game.to_fen_str();
+        let board = game.board;
+        let state = game.state;
+        let repetitions = game.repetition_count();
+        let hash = game.hash();
+
+        let undo = game.make_move(*mov);
+        check_unmake(game, depth - 1);
+        game.unmake_move(*mov, undo);
+
+        assert_eq!(game.to_fen_str(), fen, "Unmaking {mov:?}");
+        assert_eq!(game.board, board, "Unmaking {mov:?}");
+        assert_eq!(game.state, state, "Unmaking {mov:?}");
+        assert_eq!(game.repetition_count(), repetitions, "Unmaking {mov:?}");
+        assert_eq!(game.hash(), hash, "Unmaking {mov:?}");
+    }
+}
+
+#[test]
+fn unmake_start_position(){
+    check_unmake(&mut Game::default(), 3);
+}
+
+#[test]
+fn unmake_castling_and_en_passant(){
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    check_unmake(&mut Game::from_fen_str(fen).unwrap(), 2);
+}
+
+#[test]
+fn unmake_promotions(){
+    let fen = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    check_unmake(&mut Game::from_fen_str(fen).unwrap(), 2);
+}
+
+#[test]
+fn unmake_game_end(){
+    // Scholar's mate, taken back
+    let mut game = Game::from_fen_str("r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4").unwrap();
+    check_unmake(&mut game, 2);
+}
+
+#[test]
+fn unmake_restores_history_of_irreversible_moves(){
+    let mut game = Game::default();
+    for uci in ["g1f3", "g8f6", "f3g1", "f6g8"]{
+        game.make_move(game.parse_uci_move(uci).unwrap());
+    }
+    assert_eq!(game.repetition_count(), 2);
+
+    // The pawn move leaves no earlier position to repeat,
+    // and taking it back brings them back
+    let mov = game.parse_uci_move("e2e4").unwrap();
+    let undo = game.make_move(mov);
+    assert_eq!(game.repetition_count(), 1);
+    game.unmake_move(mov, undo);
+    assert_eq!(game.repetition_count(), 2);
+
+    for uci in ["g1f3", "g8f6", "f3g1", "f6g8"]{
+        game.make_move(game.parse_uci_move(uci).unwrap());
+    }
+    assert_eq!(game.repetition_count(), 3);
+}
@@ -0,0 +1,131 @@
+use libchess::game::Game;
+use libchess::zobrist;
+
+///
+/// Walk the move tree, checking the incrementally
+/// updated hash against one computed from scratch
+///
+fn check_incremental(game: &mut Game, depth: u32){
+    assert_eq!(game.hash(), game.get_zobrist_hash(zobrist::keys()), "{}", game.to_fen_str());
+    if depth == 0 {
+        return;
+    }
+    let mut mb = Vec::new();
+    for mov in game.get_legal_moves(&mut mb){
+        let undo = game.make_move(*mov);
+        check_incremental(game, depth - 1);
+        game.unmake_move(*mov, undo);
+    }
+}
+
+fn play(fen: &str, moves: &[&str]) -> Game{
+    let mut game = Game::from_fen_str(fen).unwrap();
+    for mov in moves{
+        game = game.after_move(game.parse_uci_move(mov).unwrap());
+    }
+    game
+}
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[test]
+fn incremental_matches_fresh(){
+    // Between them these cover castling, en passant, promotions and rook captures
+    for fen in [
+        START,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ]{
+        check_incremental(&mut Game::from_fen_str(fen).unwrap(), 3);
+    }
+}
+
+#[test]
+fn transpositions_share_a_hash(){
+    let a = play(START, &["g1f3", "g8f6", "b1c3", "b8c6"]);
+    let b = play(START, &["b1c3", "b8c6", "g1f3", "g8f6"]);
+    assert_eq!(a.hash(), b.hash());
+
+    // Moving the knights out and back loses nothing but the move counters
+    let c = play(START, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+    assert_eq!(c.hash(), Game::default().hash());
+}
+
+#[test]
+fn hash_covers_side_rights_and_en_passant(){
+    let base = Game::from_fen_str(START).unwrap().hash();
+    let black = Game::from_fen_str("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap().hash();
+    let no_castle = Game::from_fen_str("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1").unwrap().hash();
+    assert_ne!(base, black);
+    assert_ne!(base, no_castle);
+
+    let ep = Game::from_fen_str("rnbqkbnr/pppp1ppp/8/4pP2/8/8/PPPPP1PP/RNBQKBNR w KQkq e6 0 3").unwrap().hash();
+    let no_ep = Game::from_fen_str("rnbqkbnr/pppp1ppp/8/4pP2/8/8/PPPPP1PP/RNBQKBNR w KQkq - 0 3").unwrap().hash();
+    assert_ne!(ep, no_ep);
+}
+
+#[test]
+fn follows_polyglot_layout(){
+    // Pieces are keyed at 64 * (2 * kind + white) + square,
+    // kinds running pawn, knight, bishop, rook, queen, king
+    let back_rank = [3, 1, 2, 4, 5, 2, 1, 3];
+    let mut expected = 0;
+    for (file, kind) in back_rank.iter().enumerate(){
+        expected ^= zobrist::RANDOM64[64 * (2 * kind + 1) + file];
+        expected ^= zobrist::RANDOM64[64 * 2 * kind + 56 + file];
+        expected ^= zobrist::RANDOM64[64 + 8 + file];
+        expected ^= zobrist::RANDOM64[48 + file];
+    }
+    // All four castling rights, and white to move
+    for index in [768, 769, 770, 771, 780]{
+        expected ^= zobrist::RANDOM64[index];
+    }
+    assert_eq!(Game::default().hash().value(), expected);
+}
+
+#[test]
+fn en_passant_hashed_only_when_capturable(){
+    // No black pawn can take on e3
+    let pushed = play(START, &["e2e4"]);
+    let plain = Game::from_fen_str("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    assert_eq!(pushed.hash(), plain.hash());
+
+    // Whereas the pawn on d4 can
+    let pushed = play("rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &["e2e4"]);
+    let plain = Game::from_fen_str("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    assert_ne!(pushed.hash(), plain.hash());
+    assert_eq!(pushed.hash(), pushed.get_zobrist_hash(zobrist::keys()));
+}
+
+#[test]
+fn matches_polyglot_reference_keys(){
+    // The example positions and keys given with the Polyglot book format
+    let cases: [(&[&str], u64); 7] = [
+        (&[], 0x463b96181691fc9c),
+        (&["e2e4"], 0x823c9b50fd114196),
+        (&["e2e4", "d7d5"], 0x0756b94461c50fb0),
+        (&["e2e4", "d7d5", "e4e5"], 0x662fafb965db29d4),
+        (&["e2e4", "d7d5", "e4e5", "f7f5"], 0x22a48b5a8e47ff78),
+        (&["e2e4", "d7d5", "e4e5", "f7f5", "e1e2"], 0x652a607ca3f242c1),
+        (&["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"], 0x00fdd303c946bdd9),
+    ];
+    for (moves, key) in cases{
+        assert_eq!(play(START, moves).hash().value(), key, "{moves:?}");
+    }
+}
+
+#[test]
+fn matches_polyglot_reference_keys_with_en_passant(){
+    // The b4 pawn can take on c3, so the en passant file is hashed
+    let game = play(START, &["a2a4", "b7b5", "h2h4", "b5b4", "c2c4"]);
+    assert_eq!(game.hash().value(), 0x3c8123ea7b067637);
+
+    // Taking it, and losing white's queenside castling with the rook move
+    let game = play(START, &["a2a4", "b7b5", "h2h4", "b5b4", "c2c4", "b4c3", "a1a3"]);
+    assert_eq!(game.hash().value(), 0x5c3f9b829b279560);
+
+    // The same holds for a position read from FEN
+    let fen = Game::from_fen_str("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3").unwrap();
+    assert_eq!(fen.hash().value(), 0x22a48b5a8e47ff78);
+}
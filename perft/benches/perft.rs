@@ -1,4 +1,10 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use libchess::board::{ChessBoard, LegalityMasks};
+use libchess::game::Game;
+use libchess::piece::*;
+use std::time::Duration;
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
 
 fn perft(n : u32){
     let game = libchess::game::Game::default();
@@ -6,6 +12,31 @@ fn perft(n : u32){
     libchess_perft::perft(black_box(game), n);
 }
 
+fn count_nodes(n : u32){
+    let game = libchess::game::Game::default();
+
+    libchess_perft::count_nodes(black_box(game), n);
+}
+
+///
+/// Legal moves of every white piece, by playing out each move on a board copy
+///
+fn copied_moves<K : TPieceKind>(board : &ChessBoard) -> u64{
+    board.pieces::<GWhite, K>()
+        .positions()
+        .map(|pos| board.get_piece_legal_moves::<GWhite, K>(pos).data)
+        .fold(0, |acc, m| acc ^ m)
+}
+
+///
+/// Legal moves of every white piece, by filtering with the legality masks
+///
+fn masked_moves<K : TPieceKind>(board : &ChessBoard, masks : &LegalityMasks) -> u64{
+    board.pieces::<GWhite, K>()
+        .positions()
+        .map(|pos| board.get_piece_masked_moves::<GWhite, K>(pos, masks).data)
+        .fold(0, |acc, m| acc ^ m)
+}
 
 fn benchmark(c : &mut Criterion){
     c.bench_function("perft 5", |b| b.iter(|| perft(black_box(5))));
@@ -13,7 +44,48 @@ fn benchmark(c : &mut Criterion){
     c.bench_function("perft 7", |b| b.iter(|| perft(black_box(7))));
 }
 
-criterion_group!(benches, benchmark);
+///
+/// Bulk counting as a measure of move generation speed,
+/// reported in nodes per second
+///
+fn bulk(c : &mut Criterion){
+    let nodes = libchess_perft::count_nodes(Game::default(), 6);
+
+    let mut group = c.benchmark_group("bulk perft");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(20));
+    group.throughput(Throughput::Elements(nodes));
+    group.bench_function("6", |b| b.iter(|| count_nodes(black_box(6))));
+    group.finish();
+}
+
+fn legality(c : &mut Criterion){
+    let board = Game::from_fen_str(KIWIPETE).unwrap().board;
+
+    let mut group = c.benchmark_group("kiwipete legal moves");
+    group.bench_function("board copies", |b| b.iter(|| {
+        let board = black_box(&board);
+        copied_moves::<GPawn>(board)
+            ^ copied_moves::<GKnight>(board)
+            ^ copied_moves::<GBishop>(board)
+            ^ copied_moves::<GRook>(board)
+            ^ copied_moves::<GQueen>(board)
+            ^ copied_moves::<GKing>(board)
+    }));
+    group.bench_function("masks", |b| b.iter(|| {
+        let board = black_box(&board);
+        let masks = &board.legality_masks::<GWhite>();
+        masked_moves::<GPawn>(board, masks)
+            ^ masked_moves::<GKnight>(board, masks)
+            ^ masked_moves::<GBishop>(board, masks)
+            ^ masked_moves::<GRook>(board, masks)
+            ^ masked_moves::<GQueen>(board, masks)
+            ^ masked_moves::<GKing>(board, masks)
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, benchmark, bulk, legality);
 
 
 criterion_main!(benches);
@@ -1,23 +1,71 @@
+//!
+//! Usage: perft <depth> [--fen <fen>] [--divide] [--bulk]
+//!
+//! With `--divide` the nodes below each root move are listed
+//! in the format other engines use, to help bisect differences.
+//! With `--bulk` only nodes are counted, as a move generation benchmark
+//!
+
+fn usage() -> ! {
+    eprintln!("Usage: perft <depth> [--fen <fen>] [--divide] [--bulk]");
+    std::process::exit(1);
+}
+
 fn main(){
-    let args : Vec<_> = std::env::args().collect();
+    let args : Vec<_> = std::env::args().skip(1).collect();
+
+    let ply = args.first().and_then(|p| p.parse::<u32>().ok()).unwrap_or_else(|| usage());
 
-    let ply = args.get(1).expect("Expected 'ply' argument").parse::<u32>().expect("Expected valid ply");
+    let mut fen = None;
+    let mut divide = false;
+    let mut bulk = false;
+    let mut rest = args[1..].iter().peekable();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            // The FEN may be passed quoted or as separate words
+            "--fen" => {
+                let mut words = Vec::new();
+                while let Some(word) = rest.next_if(|w| !w.starts_with("--")) {
+                    words.push(word.as_str());
+                }
+                fen = Some(words.join(" "));
+            }
+            "--divide" => divide = true,
+            "--bulk" => bulk = true,
+            _ => usage(),
+        }
+    }
 
-    let board = libchess::game::Game::default();
+    let board = match fen {
+        Some(fen) => libchess::game::Game::from_fen_str(&fen).unwrap_or_else(|e| {
+            eprintln!("Invalid FEN '{fen}': {e}");
+            std::process::exit(1);
+        }),
+        None => libchess::game::Game::default(),
+    };
 
     let begin = std::time::Instant::now();
 
-    #[cfg(not(feature = "zobrist"))]
-    let result = libchess_perft::perft(board, ply);
+    if divide {
+        let counts = libchess_perft::divide(&board, ply);
+        for (mov, nodes) in &counts {
+            println!("{}: {nodes}", mov.to_uci());
+        }
+        let total: u64 = counts.iter().map(|(_, nodes)| nodes).sum();
+        println!();
+        println!("Nodes searched: {total} [{:?}]", begin.elapsed());
+        return;
+    }
 
+    if bulk {
+        let nodes = libchess_perft::count_nodes(board, ply);
+        let elapsed = begin.elapsed();
+        let nps = nodes as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        println!("Perft({ply}) = {nodes} [{elapsed:?}, {nps:.0} nps]");
+        return;
+    }
 
-    #[cfg(feature = "zobrist")]
-    let result = {
-        // let mut rng = rand::rngs::StdRng::from_seed(std::array::from_fn(|i| (i * 6 + 5) as u8));
-        let mut rng = rand::thread_rng();
-        let zkeys = libchess::zobrist::ZobKeys::generate(&mut rng);
-        libchess_perft::perft(board, ply, &zkeys)
-    };
+    let result = libchess_perft::perft(board, ply);
 
     let elapsed = begin.elapsed();
     println!("Perft({ply}) = {result:?} [{elapsed:?}]");
@@ -0,0 +1,69 @@
+//!
+//! Usage: perftsuite <suite.epd> [--max-depth N]
+//!
+//! Runs every position of a perft suite, reporting each one
+//! and exiting with a failure if any node count is wrong
+//!
+
+use libchess_perft::epd;
+
+///
+/// Deepest annotation checked unless `--max-depth` says otherwise
+///
+const DEFAULT_MAX_DEPTH: u32 = 4;
+
+fn usage() -> ! {
+    eprintln!("Usage: perftsuite <suite.epd> [--max-depth N]");
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut path = None;
+    let mut max_depth = DEFAULT_MAX_DEPTH;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-depth" => max_depth = args.next().and_then(|d| d.parse().ok()).unwrap_or_else(|| usage()),
+            flag if flag.starts_with("--") => usage(),
+            _ if path.is_none() => path = Some(arg),
+            _ => usage(),
+        }
+    }
+    let Some(path) = path else {
+        usage();
+    };
+
+    let positions = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|epd| epd::parse_epd(&epd).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            eprintln!("Could not load {path}: {e}");
+            std::process::exit(2);
+        });
+
+    let begin = std::time::Instant::now();
+    let results = epd::run_suite(&positions, max_depth);
+
+    let mut failures = 0;
+    for (number, (position, results)) in positions.iter().zip(&results).enumerate() {
+        let passed = results.iter().all(epd::DepthResult::passed);
+        println!("{} #{} {}", if passed { "PASS" } else { "FAIL" }, number + 1, position.fen);
+
+        for result in results {
+            let verdict = if result.passed() { "ok" } else { "MISMATCH" };
+            println!(
+                "    D{} {} expected {} [{:?}] {verdict}",
+                result.depth, result.actual, result.expected, result.elapsed
+            );
+        }
+        if !passed {
+            failures += 1;
+        }
+    }
+
+    println!("{} of {} positions passed [{:?}]", positions.len() - failures, positions.len(), begin.elapsed());
+    if failures > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,125 @@
+//!
+//! Perft test suites
+//!
+//! Positions are read from EPD lines annotated with their expected
+//! node counts, as in `<fen> ;D1 20 ;D2 400`, and checked against `perft`
+//!
+
+use libchess::game::{FenParseError, Game};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, thiserror::Error)]
+pub enum EpdParseError {
+    #[error("Line {line}: {source}")]
+    BadFen { line: usize, source: FenParseError },
+    #[error("Line {line}: Malformed depth annotation '{annotation}'")]
+    BadAnnotation { line: usize, annotation: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct EpdPosition {
+    pub fen: String,
+
+    ///
+    /// Expected node counts, as pairs of depth and count
+    ///
+    pub expected: Vec<(u32, u64)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DepthResult {
+    pub depth: u32,
+    pub expected: u64,
+    pub actual: u64,
+    pub elapsed: Duration,
+}
+
+impl DepthResult {
+    pub const fn passed(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+///
+/// Read every position of an EPD suite,
+/// skipping blank lines and `#` comments
+///
+pub fn parse_epd(epd: &str) -> Result<Vec<EpdPosition>, EpdParseError> {
+    let mut positions = Vec::new();
+    for (index, text) in epd.lines().enumerate() {
+        let line = index + 1;
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = text.split(';');
+        let fen = fields.next().unwrap_or_default().trim().to_owned();
+        Game::from_fen_str(&fen).map_err(|source| EpdParseError::BadFen { line, source })?;
+
+        let expected = fields
+            .map(str::trim)
+            .filter(|annotation| !annotation.is_empty())
+            .map(|annotation| {
+                let bad = || EpdParseError::BadAnnotation {
+                    line,
+                    annotation: annotation.to_owned(),
+                };
+                let (depth, count) = annotation.strip_prefix('D').and_then(|a| a.split_once(' ')).ok_or_else(bad)?;
+                Ok((depth.parse().map_err(|_| bad())?, count.trim().parse().map_err(|_| bad())?))
+            })
+            .collect::<Result<_, _>>()?;
+
+        positions.push(EpdPosition { fen, expected });
+    }
+    Ok(positions)
+}
+
+///
+/// Check a position at each of its annotated depths, up to the given limit
+///
+pub fn run_position(position: &EpdPosition, max_depth: u32) -> Vec<DepthResult> {
+    let game = Game::from_fen_str(&position.fen).expect("Suite positions are validated when parsed");
+    position
+        .expected
+        .iter()
+        .filter(|&&(depth, _)| depth <= max_depth)
+        .map(|&(depth, expected)| {
+            let begin = Instant::now();
+            let actual = crate::perft(game.clone(), depth).nodes;
+            DepthResult {
+                depth,
+                expected,
+                actual,
+                elapsed: begin.elapsed(),
+            }
+        })
+        .collect()
+}
+
+///
+/// Check every position of a suite, spread across the available cores
+///
+/// Results are in the same order as the positions
+///
+pub fn run_suite(positions: &[EpdPosition], max_depth: u32) -> Vec<Vec<DepthResult>> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![Vec::new(); positions.len()]);
+    let workers = std::thread::available_parallelism().map_or(1, usize::from).min(positions.len());
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(position) = positions.get(index) else {
+                    break;
+                };
+                let result = run_position(position, max_depth);
+                results.lock().unwrap_or_else(std::sync::PoisonError::into_inner)[index] = result;
+            });
+        }
+    });
+    results.into_inner().unwrap_or_else(std::sync::PoisonError::into_inner)
+}
@@ -6,16 +6,45 @@
     clippy::perf
 )]
 
+pub mod epd;
+
 #[cfg(feature = "parallelism")]
 use rayon::prelude::*;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct PerftKey {
-    depth: u32,
-    hash: libchess::zobrist::ZobristHash,
+#[cfg(feature = "zobrist")]
+use libchess::tt::{TableEntry, TranspositionTable};
+
+///
+/// Size of the table used to share results between transpositions
+///
+#[cfg(feature = "zobrist")]
+const TABLE_MB: usize = 64;
+
+///
+/// The results below a position, searched to the given depth
+///
+#[cfg(feature = "zobrist")]
+#[derive(Clone, Copy, Debug, Default)]
+struct PerftEntry {
+    depth: u8,
+    results: PerftResults,
+}
+
+#[cfg(feature = "zobrist")]
+impl TableEntry for PerftEntry {
+    fn depth(&self) -> u8 {
+        self.depth
+    }
 }
 
-#[derive(Clone, Debug, Default)]
+///
+/// Counts of the leaf positions, in the columns of the
+/// Chess Programming Wiki perft tables
+///
+/// A check by any piece other than the one which moved is a discovered
+/// check, though double checks are only counted as such
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct PerftResults {
     pub nodes: u64,
     pub captures: u64,
@@ -25,6 +54,9 @@ pub struct PerftResults {
     pub queenside_castles: u64,
     pub promotions: u64,
     pub regulars: u64,
+    pub checks: u64,
+    pub discovery_checks: u64,
+    pub double_checks: u64,
     pub checkmates: u64,
     pub stalemates: u64,
 }
@@ -41,6 +73,9 @@ impl std::ops::Add for PerftResults {
             kingside_castles: self.kingside_castles + rhs.kingside_castles,
             queenside_castles: self.queenside_castles + rhs.queenside_castles,
             regulars: self.regulars + rhs.regulars,
+            checks: self.checks + rhs.checks,
+            discovery_checks: self.discovery_checks + rhs.discovery_checks,
+            double_checks: self.double_checks + rhs.double_checks,
             checkmates: self.checkmates + rhs.checkmates,
             stalemates: self.stalemates + rhs.stalemates,
         }
@@ -54,150 +89,242 @@ impl std::iter::Sum for PerftResults {
 }
 
 fn _perft(
-    game: &libchess::game::Game,
+    game: &mut libchess::game::Game,
     depth: u32,
-    #[cfg(feature = "zobrist")] map: &dashmap::DashMap<PerftKey, PerftResults>,
-    #[cfg(feature = "zobrist")] hash: libchess::zobrist::ZobristHash,
-    #[cfg(feature = "zobrist")] zkeys: &libchess::zobrist::ZobKeys,
+    #[cfg(feature = "zobrist")] table: &TranspositionTable<PerftEntry>,
 ) -> PerftResults {
     #[cfg(feature = "zobrist")]
-    if let Some(val) = map.get(&PerftKey {
-        hash: hash.clone(),
-        depth,
-    }) {
-        return val.clone();
+    if let Some(entry) = table.probe(&game.hash()).filter(|e| u32::from(e.depth) == depth) {
+        return entry.results;
+    }
+    let moves = game.legal_moves();
+    if depth == 1 {
+        return moves.iter().map(|&mov| leaf_results(game, mov)).sum();
     }
-    let mut mb = Vec::new();
-    mb.reserve(80);
-
-    match game.get_all_moves(&mut mb) {
-        Ok(moves) => {
-            if moves.is_empty(){
-                return PerftResults{
-                    stalemates: 1,
-                    nodes: 0,
-                    ..Default::default()
-                }
-            }
-            if depth == 1 {
-                let mut captures = 0;
-                let mut castles = 0;
-                let mut enpassant = 0;
-                let mut promotions = 0;
-                let mut regulars = 0;
-                let mut kingside_castles = 0;
-                let mut queenside_castles = 0;
-                let nodes = moves.len() as u64;
-
-                for mov in moves {
-                    match mov {
-                        libchess::game::GameMove::Capture(_) => captures += 1,
-                        libchess::game::GameMove::CastleKingside(_) => {
-                            castles += 1;
-                            kingside_castles += 1;
-                        }
-                        libchess::game::GameMove::CastleQueenside(_) => {
-                            castles += 1;
-                            queenside_castles += 1;
-                        }
-                        libchess::game::GameMove::Enpassant(_) => {
-                            captures += 1;
-                            enpassant += 1;
-                        }
-                        libchess::game::GameMove::CapturePromote { .. } => {
-                            captures += 1;
-                            promotions += 1;
-                        }
-                        libchess::game::GameMove::Promote { .. } => {
-                            promotions += 1;
-                        }
-                        libchess::game::GameMove::Regular(_) => {
-                            regulars += 1;
-                        }
-                    }
-                }
-                return PerftResults {
-                    nodes,
-                    castles,
-                    captures,
-                    enpassant,
-                    promotions,
-                    kingside_castles,
-                    queenside_castles,
-                    regulars,
-                    checkmates: 0,
-                    stalemates: 0,
-                };
-            }
-
-            #[cfg(feature = "parallelism")]
-            let it = moves.par_iter();
-            #[cfg(not(feature = "parallelism"))]
-            let it = moves.iter();
-            it.map(|mov| {
-                let mut cl = game.clone();
-                #[cfg(feature = "zobrist")]
-                let hcl = {
-                    let mut hcl = hash.clone();
-                    cl.make_move(*mov, &mut hcl, zkeys);
-                    hcl
-                };
-
-                #[cfg(not(feature = "zobrist"))]
-                cl.make_move(*mov);
-                #[allow(clippy::let_and_return)]
-                let v = _perft(
-                    &cl,
-                    depth - 1,
-                    #[cfg(feature = "zobrist")]
-                    map,
-                    #[cfg(feature = "zobrist")]
-                    hcl.clone(),
-                    #[cfg(feature = "zobrist")]
-                    zkeys,
-                );
 
+    let results = moves
+        .iter()
+        .map(|mov| {
+            perft_child(
+                game,
+                *mov,
+                depth,
                 #[cfg(feature = "zobrist")]
-                map.insert(PerftKey { hash: hcl, depth }, v.clone());
-                v
-            })
-            .sum()
-        },
-        Err(e) => {
-            use libchess::game::MoveGenerationError;
-            use libchess::game::GameState;
-            match e{
-                MoveGenerationError::GameFinished(GameState::Stalemate) => PerftResults{
-                    stalemates: 1,
-                    nodes: 1,
-                    ..Default::default()
-                },
-                MoveGenerationError::GameFinished(GameState::WhiteVictory | GameState::BlackVictory) => {
-                    println!("CM: {}", game.to_fen_str());
-                    PerftResults{
-                        checkmates: 1,
-                        nodes: 1,
-                        ..Default::default()
-                }},
-                _ => unreachable!()
-            }
+                table,
+            )
+        })
+        .sum();
+
+    #[cfg(feature = "zobrist")]
+    table.store(
+        &game.hash(),
+        PerftEntry {
+            depth: depth as u8,
+            results,
         },
+    );
+    results
+}
+
+///
+/// Classify a move made at the last ply, and the position it leads to
+///
+fn leaf_results(game: &mut libchess::game::Game, mov: libchess::game::GameMove) -> PerftResults {
+    use libchess::game::{GameMove, GameState};
+
+    let mut results = PerftResults {
+        nodes: 1,
+        ..Default::default()
+    };
+    match mov {
+        GameMove::Capture(_) => results.captures = 1,
+        GameMove::CastleKingside(_) => {
+            results.castles = 1;
+            results.kingside_castles = 1;
+        }
+        GameMove::CastleQueenside(_) => {
+            results.castles = 1;
+            results.queenside_castles = 1;
+        }
+        GameMove::Enpassant(_) => {
+            results.captures = 1;
+            results.enpassant = 1;
+        }
+        GameMove::CapturePromote { .. } => {
+            results.captures = 1;
+            results.promotions = 1;
+        }
+        GameMove::Promote { .. } => results.promotions = 1,
+        GameMove::Regular(_) => results.regulars = 1,
+    }
+
+    // When castling it is the rook which may give check directly
+    let moved_to = match mov {
+        GameMove::CastleKingside(team) | GameMove::CastleQueenside(team) => {
+            let row = match team {
+                libchess::piece::Team::White => 0,
+                libchess::piece::Team::Black => 7,
+            };
+            let col = if matches!(mov, GameMove::CastleKingside(_)) { 5 } else { 3 };
+            positioning::Position::new(row, col)
+        }
+        _ => mov.to_pos(),
+    };
+
+    let undo = game.make_move(mov);
+    let checkers = game.checkers();
+    if !checkers.empty() {
+        results.checks = 1;
+        if checkers.count() > 1 {
+            results.double_checks = 1;
+        } else if checkers != positioning::Bitboard::from(moved_to) {
+            results.discovery_checks = 1;
+        }
     }
+    match game.state {
+        GameState::WhiteVictory | GameState::BlackVictory => results.checkmates = 1,
+        GameState::Draw {
+            reason: libchess::game::DrawReason::Stalemate,
+            ..
+        } => results.stalemates = 1,
+        _ => {}
+    }
+    game.unmake_move(mov, undo);
+
+    results
 }
 
-pub fn perft(
-    game: libchess::game::Game,
-    limit: u32,
-    #[cfg(feature = "zobrist")] zkeys: &libchess::zobrist::ZobKeys,
+///
+/// Count the results below a single move, taking it back afterwards
+///
+fn perft_child(
+    game: &mut libchess::game::Game,
+    mov: libchess::game::GameMove,
+    depth: u32,
+    #[cfg(feature = "zobrist")] table: &TranspositionTable<PerftEntry>,
 ) -> PerftResults {
+    let undo = game.make_move(mov);
+
+    let v = _perft(
+        game,
+        depth - 1,
+        #[cfg(feature = "zobrist")]
+        table,
+    );
+
+    game.unmake_move(mov, undo);
+    v
+}
+
+///
+/// Count and classify the positions `limit` plies below the game
+///
+/// At a depth of zero the game itself is the only position counted.
+/// Drawn positions are counted through, here and in `count_nodes`,
+/// as perft only measures move generation
+///
+pub fn perft(mut game: libchess::game::Game, limit: u32) -> PerftResults {
+    if limit == 0 {
+        return PerftResults {
+            nodes: 1,
+            ..Default::default()
+        };
+    }
+    #[cfg(feature = "zobrist")]
+    let table = TranspositionTable::new(TABLE_MB);
+
+    // Split the work across the root moves, with each thread
+    // making and unmaking moves on its own copy of the game
+    #[cfg(feature = "parallelism")]
+    if limit > 1 {
+        let moves = game.legal_moves();
+        if !moves.is_empty() {
+            return moves
+                .par_iter()
+                .map(|mov| {
+                    let mut cl = game.clone();
+                    perft_child(
+                        &mut cl,
+                        *mov,
+                        limit,
+                        #[cfg(feature = "zobrist")]
+                        &table,
+                    )
+                })
+                .sum();
+        }
+    }
+
     _perft(
-        &game,
+        &mut game,
         limit,
         #[cfg(feature = "zobrist")]
-        &dashmap::DashMap::new(),
-        #[cfg(feature = "zobrist")]
-        game.get_zobrist_hash(zkeys).unwrap(),
-        #[cfg(feature = "zobrist")]
-        zkeys,
+        &table,
     )
 }
+
+fn _count_nodes(game: &mut libchess::game::Game, depth: u32) -> u64 {
+    let moves = game.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for mov in moves {
+        let undo = game.make_move(mov);
+        nodes += _count_nodes(game, depth - 1);
+        game.unmake_move(mov, undo);
+    }
+    nodes
+}
+
+///
+/// Count only the leaf nodes, skipping the classification `perft` does
+///
+/// Leaves are counted straight from the length of the move list,
+/// so this measures move generation speed. At a depth of zero
+/// the game itself is the one node counted
+///
+pub fn count_nodes(mut game: libchess::game::Game, limit: u32) -> u64 {
+    if limit == 0 {
+        return 1;
+    }
+
+    // Split the work across the root moves, as `perft` does
+    #[cfg(feature = "parallelism")]
+    if limit > 1 {
+        let moves = game.legal_moves();
+        return moves
+            .par_iter()
+            .map_init(
+                || game.clone(),
+                |game, &mov| {
+                    let undo = game.make_move(mov);
+                    let nodes = _count_nodes(game, limit - 1);
+                    game.unmake_move(mov, undo);
+                    nodes
+                },
+            )
+            .sum();
+    }
+
+    _count_nodes(&mut game, limit)
+}
+
+///
+/// Count the nodes below each root move, for comparing
+/// move generation against a reference engine
+///
+pub fn divide(game: &libchess::game::Game, limit: u32) -> Vec<(libchess::game::GameMove, u64)> {
+    if limit == 0 {
+        return Vec::new();
+    }
+    game.legal_moves()
+        .iter()
+        .map(|&mov| {
+            let mut after = game.clone();
+            after.make_move(mov);
+            (mov, count_nodes(after, limit - 1))
+        })
+        .collect()
+}
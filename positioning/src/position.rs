@@ -43,6 +43,23 @@ impl Position{
 
         format!("{col}{row}")
     }
+
+    ///
+    /// Parse a position from its algebraic name, i.e `e4`
+    /// both upper and lower case columns are accepted
+    ///
+    pub fn from_alphanum(name : &str) -> Option<Self>{
+        let &[col, row] = name.as_bytes() else {
+            return None;
+        };
+        let col = col.to_ascii_lowercase();
+
+        if !(b'a'..=b'h').contains(&col) || !(b'1'..=b'8').contains(&row){
+            return None;
+        }
+
+        Some(Self::new(row - b'1', col - b'a'))
+    }
 }
 
 
@@ -1,30 +1,71 @@
+use libchess::book::Book;
+use libchess::eval::{Evaluator, MaterialEvaluator, TaperedEvaluator};
+use libchess::game::{Game, GameMove};
 use rand::prelude::*;
 use std::fs;
+
+///
+/// Pick one of the moves, greedily by evaluation when an evaluator
+/// is given and uniformly at random otherwise
+///
+fn choose_move(game: &Game, moves: &[GameMove], evaluator: Option<&dyn Evaluator>, rng: &mut impl Rng) -> Option<GameMove> {
+    let Some(evaluator) = evaluator else {
+        return moves.choose(rng).copied();
+    };
+
+    let scored: Vec<_> = moves
+        .iter()
+        .map(|&m| {
+            let after = game.after_move(m);
+            (m, -evaluator.evaluate(&after.board, after.side_to_move()))
+        })
+        .collect();
+    let best = scored.iter().map(|&(_, s)| s).max()?;
+
+    // Ties are broken randomly so games still differ from one another
+    let candidates: Vec<_> = scored.iter().filter(|&&(_, s)| s == best).map(|&(m, _)| m).collect();
+    candidates.choose(rng).copied()
+}
+
 fn main() {
+    let evaluator: Option<Box<dyn Evaluator>> = match std::env::args().nth(1).as_deref() {
+        None | Some("random") => None,
+        Some("material") => Some(Box::new(MaterialEvaluator)),
+        Some("tapered") => Some(Box::new(TaperedEvaluator)),
+        Some(other) => {
+            eprintln!("Unknown evaluator '{other}', expected one of random, material or tapered");
+            std::process::exit(1);
+        }
+    };
+
+    // Games open from the book, when one is given, until it runs out of moves
+    let book = std::env::args().nth(2).map(|path| {
+        Book::open(&path).unwrap_or_else(|e| {
+            eprintln!("Could not open book '{path}': {e}");
+            std::process::exit(1);
+        })
+    });
 
     let simcnt = 1000000;
     let mut rng = rand::thread_rng();
 
-    let mut move_buf = Vec::new();
-
-    let mut bar = indicatif::ProgressBar::new(simcnt); 
+    let bar = indicatif::ProgressBar::new(simcnt); 
 
-    fs::create_dir_all("games");
-    for sim in 0..simcnt{
+    fs::create_dir_all("games").expect("Failed to create the games directory");
+    for _ in 0..simcnt{
         bar.inc(1); 
         // let mut sf = fs::OpenOptions::new().write(true).create(true).open(format!("games/{sim}.sim")).unwrap();
         let mut game = libchess::game::Game::default();
-        use std::io::Write;
-        for i in 1..{
+        loop{
             game.board.verify();
-            move_buf.clear();
             // sf.write_all(game.to_fen_str().as_bytes()).unwrap();
             // sf.flush().unwrap();
-            match game.get_all_moves(&mut move_buf){
+            match game.moves(){
                 Ok(moves) => {
-                    if let Some(chosen_move) = moves.choose(&mut rng){
+                    let book_move = book.as_ref().and_then(|b| b.pick_weighted(&game, &mut rng));
+                    if let Some(chosen_move) = book_move.or_else(|| choose_move(&game, &moves, evaluator.as_deref(), &mut rng)){
                         // sf.write_all(format!("; {chosen_move:?}").as_bytes()).unwrap();
-                        game.make_move(*chosen_move);
+                        game.make_move(chosen_move);
                     }
                     else{
                         // println!("~ Stalemate");